@@ -0,0 +1,448 @@
+//! Taking addresses apart: house, unit and lot numbers ([Premises]), the API's property names
+//! ([PropertyName]) and the "suburb, state, postcode" end of an address ([LocalityParts]), so that
+//! "2 Boundary St" doesn't match "12 Boundary St" or "2/22 Boundary St"
+
+use std::fmt::Display;
+
+/// Words that introduce a unit number, e.g. "Unit 3" or "Shop 1"
+const UNIT_PREFIXES: &[&str] = &[
+    "unit",
+    "u",
+    "flat",
+    "apartment",
+    "apt",
+    "shop",
+    "suite",
+    "villa",
+    "townhouse",
+];
+
+/// Australian states and territories, as abbreviations and in full (lowercase), which
+/// people put after the suburb
+const STATES: &[(&str, &str)] = &[
+    ("QLD", "queensland"),
+    ("NSW", "new south wales"),
+    ("VIC", "victoria"),
+    ("TAS", "tasmania"),
+    ("SA", "south australia"),
+    ("WA", "western australia"),
+    ("NT", "northern territory"),
+    ("ACT", "australian capital territory"),
+];
+
+/// The word that introduces a lot number, for blocks that don't have a street number yet
+const LOT_PREFIX: &str = "lot";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A house number, e.g. "12", "12A", "12-14" or "3/12"
+pub struct HouseNumber {
+    /// The unit or flat, e.g. "3" in "3/12"
+    pub unit: Option<String>,
+    /// The street number, e.g. 12 in "12A"
+    pub number: u32,
+    /// A letter after the number, uppercased, e.g. "A" in "12A"
+    pub suffix: Option<String>,
+    /// The last number of a range, e.g. 14 in "12-14"
+    pub last: Option<u32>,
+}
+
+/// Split "12A" into 12 and "A"
+fn split_suffix(value: &str) -> Option<(u32, Option<String>)> {
+    let digits = value.chars().take_while(char::is_ascii_digit).count();
+    let number = value.get(..digits)?.parse().ok()?;
+    let suffix = value.get(digits..)?;
+    if suffix.is_empty() {
+        Some((number, None))
+    } else if suffix.chars().all(|c| c.is_ascii_alphabetic()) {
+        Some((number, Some(suffix.to_uppercase())))
+    } else {
+        None
+    }
+}
+
+impl HouseNumber {
+    /// Parse a house number token, returning `None` if it isn't one
+    pub fn parse(value: &str) -> Option<Self> {
+        let (unit, number) = match value.split_once('/') {
+            Some((unit, number)) if !unit.is_empty() => (Some(unit.to_uppercase()), number),
+            Some(_) => return None,
+            None => (None, value),
+        };
+        let (first, last) = match number.split_once('-') {
+            Some((first, last)) => (first, Some(last)),
+            None => (number, None),
+        };
+        let (number, suffix) = split_suffix(first)?;
+        let last = match last {
+            Some(last) => match split_suffix(last)? {
+                (last, None) if last > number => Some(last),
+                _ => return None,
+            },
+            None => None,
+        };
+        Some(Self {
+            unit,
+            number,
+            suffix,
+            last,
+        })
+    }
+
+    /// Is `wanted` covered by this one, ignoring the unit? Ranges cover the numbers in them on the same
+    /// side of the street, so "12-14" covers 12 and 14 but not 13. Suffixes have to match exactly.
+    pub fn covers(&self, wanted: &HouseNumber) -> bool {
+        if self.suffix != wanted.suffix {
+            return false;
+        }
+        match (self.last, wanted.last) {
+            (Some(last), None) => {
+                // a range with ends on both sides of the street can't tell us which side it's on
+                let same_side = self.number % 2 != last % 2 || wanted.number % 2 == last % 2;
+                same_side && (self.number..=last).contains(&wanted.number)
+            }
+            _ => self.number == wanted.number && self.last == wanted.last,
+        }
+    }
+}
+
+impl Display for HouseNumber {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(unit) = &self.unit {
+            write!(f, "{unit}/")?;
+        }
+        write!(f, "{}", self.number)?;
+        if let Some(suffix) = &self.suffix {
+            write!(f, "{suffix}")?;
+        }
+        if let Some(last) = self.last {
+            write!(f, "-{last}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Which property on a street an address is for, a house number, a lot number or both
+pub struct Premises {
+    /// The house number, including any unit
+    pub number: Option<HouseNumber>,
+    /// The lot number, e.g. "5" in "Lot 5"
+    pub lot: Option<String>,
+}
+
+/// Strip the punctuation people put after prefixes and numbers, e.g. "Unit 3," or "Apt."
+fn clean(word: &str) -> &str {
+    word.trim_end_matches([',', '.', ':'])
+}
+
+impl Premises {
+    /// Parse the premises from the start of `words`, e.g. "Unit 3 12 Smith St", "3/12 Smith St" or
+    /// "Lot 5 Smith St". Returns it with the number of words used, or `None` if there isn't one.
+    pub fn parse(words: &[&str]) -> Option<(Self, usize)> {
+        let mut index = 0;
+        let mut unit = None;
+        let mut lot = None;
+        let word = |index: usize| words.get(index).map(|word| clean(word));
+
+        if let Some(prefix) = word(index) {
+            if UNIT_PREFIXES.contains(&prefix.to_lowercase().as_str()) {
+                unit = Some(word(index + 1)?.to_uppercase());
+                index += 2;
+            } else if prefix.eq_ignore_ascii_case(LOT_PREFIX) {
+                lot = Some(word(index + 1)?.to_uppercase());
+                index += 2;
+            }
+        }
+
+        let number = word(index).and_then(HouseNumber::parse);
+        if number.is_some() {
+            index += 1;
+        }
+        let number = match (number, unit) {
+            (Some(number), None) => Some(number),
+            (Some(number), Some(unit)) if number.unit.is_none() => Some(HouseNumber {
+                unit: Some(unit),
+                ..number
+            }),
+            // "Unit 3 4/12" doesn't make sense
+            (Some(_), Some(_)) => return None,
+            // "Unit 3" needs a street number to go with it
+            (None, Some(_)) => return None,
+            (None, None) => None,
+        };
+        if number.is_none() && lot.is_none() {
+            return None;
+        }
+        Some((Self { number, lot }, index))
+    }
+}
+
+impl Display for Premises {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.lot, &self.number) {
+            (Some(lot), Some(number)) => write!(f, "Lot {lot} {number}"),
+            (Some(lot), None) => write!(f, "Lot {lot}"),
+            (None, Some(number)) => write!(f, "{number}"),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A property name from the API, split into the premises and street, e.g. "2/22 Boundary St"
+pub struct PropertyName {
+    /// The house and/or lot number
+    pub premises: Premises,
+    /// The rest of the name, e.g. "Boundary St"
+    pub street: String,
+}
+
+impl PropertyName {
+    /// Parse a property name, returning `None` if it doesn't start with a house or lot number
+    pub fn parse(name: &str) -> Option<Self> {
+        let words = name.split_whitespace().collect::<Vec<_>>();
+        let (premises, used) = Premises::parse(&words)?;
+        Some(Self {
+            premises,
+            street: words.get(used..).unwrap_or_default().join(" "),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// The end of an address, e.g. "Paddington QLD 4064", split into its parts
+pub struct LocalityParts {
+    /// The suburb, e.g. "Paddington"
+    pub suburb: String,
+    /// The state abbreviation, e.g. "QLD"
+    pub state: Option<String>,
+    /// The four digit postcode, e.g. "4064"
+    pub postcode: Option<String>,
+}
+
+impl LocalityParts {
+    /// Split a trailing postcode and state off a suburb, in either order, e.g. "Paddington QLD 4064"
+    pub fn parse(value: &str) -> Self {
+        let mut words = value.split_whitespace().collect::<Vec<_>>();
+        let mut parts = Self::default();
+        while let Some(last) = words.last().map(|word| clean(word)) {
+            if parts.postcode.is_none()
+                && last.len() == 4
+                && last.chars().all(|c| c.is_ascii_digit())
+            {
+                parts.postcode = Some(last.to_string());
+                words.pop();
+                continue;
+            }
+            if parts.state.is_some() {
+                break;
+            }
+            let lower = words.join(" ").to_lowercase();
+            let state = STATES.iter().find_map(|(abbreviation, name)| {
+                if last.eq_ignore_ascii_case(abbreviation) {
+                    Some((*abbreviation, 1, true))
+                } else if lower == *name || lower.ends_with(&format!(" {name}")) {
+                    Some((*abbreviation, name.split_whitespace().count(), false))
+                } else {
+                    None
+                }
+            });
+            match state {
+                // don't eat the whole thing for a full name, "Victoria" could be the suburb
+                Some((abbreviation, count, short)) if short || count < words.len() => {
+                    parts.state = Some(abbreviation.to_string());
+                    words.truncate(words.len() - count);
+                }
+                _ => break,
+            }
+        }
+        parts.suburb = words.join(" ");
+        parts
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+/// How well a property matches a house number
+pub enum NumberMatch {
+    /// Same number and same unit (or neither has a unit)
+    Exact,
+    /// The property is a range of numbers that includes the one asked for, e.g. 2 in "2-4"
+    Range,
+    /// Same number, but the property is a unit and no unit was asked for
+    AnyUnit,
+    /// Not the same property
+    None,
+}
+
+/// Compare the house number asked for with a property's
+pub fn match_number(wanted: &HouseNumber, property: &HouseNumber) -> NumberMatch {
+    if !property.covers(wanted) {
+        return NumberMatch::None;
+    }
+    let same_unit = match (&wanted.unit, &property.unit) {
+        (None, None) => true,
+        (Some(wanted), Some(unit)) => wanted == unit,
+        (None, Some(_)) => return NumberMatch::AnyUnit,
+        (Some(_), None) => false,
+    };
+    match (same_unit, property.last, wanted.last) {
+        (false, _, _) => NumberMatch::None,
+        (true, Some(_), None) => NumberMatch::Range,
+        (true, _, _) => NumberMatch::Exact,
+    }
+}
+
+/// Compare the premises asked for with a property's, by house number if one was given, otherwise by lot
+pub fn match_premises(wanted: &Premises, property: &Premises) -> NumberMatch {
+    match (&wanted.number, &property.number, &wanted.lot, &property.lot) {
+        (Some(wanted), Some(number), _, _) => match_number(wanted, number),
+        (None, _, Some(wanted), Some(lot)) if wanted == lot => NumberMatch::Exact,
+        _ => NumberMatch::None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn number(value: &str) -> HouseNumber {
+        HouseNumber::parse(value).expect("valid house number")
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            number("12a"),
+            HouseNumber {
+                unit: None,
+                number: 12,
+                suffix: Some("A".to_string()),
+                last: None,
+            }
+        );
+        assert_eq!(number("3/12-14").to_string(), "3/12-14");
+        assert_eq!(number("3/12").unit.as_deref(), Some("3"));
+        assert!(HouseNumber::parse("Boundary").is_none());
+        assert!(HouseNumber::parse("/12").is_none());
+        assert!(HouseNumber::parse("14-12").is_none());
+
+        let name = PropertyName::parse("2/22 Boundary St").expect("valid property name");
+        assert_eq!(name.premises.number.map(|n| n.number), Some(22));
+        assert_eq!(name.street, "Boundary St");
+        assert!(PropertyName::parse("Boundary St").is_none());
+    }
+
+    #[test]
+    fn test_locality_parts() {
+        let parts = LocalityParts::parse("Paddington QLD 4064");
+        assert_eq!(parts.suburb, "Paddington");
+        assert_eq!(parts.state.as_deref(), Some("QLD"));
+        assert_eq!(parts.postcode.as_deref(), Some("4064"));
+
+        let parts = LocalityParts::parse("West End 4101 Queensland");
+        assert_eq!(parts.suburb, "West End");
+        assert_eq!(parts.state.as_deref(), Some("QLD"));
+        assert_eq!(parts.postcode.as_deref(), Some("4101"));
+
+        let parts = LocalityParts::parse("QLD 4064");
+        assert_eq!(parts.suburb, "");
+        assert_eq!(parts.postcode.as_deref(), Some("4064"));
+
+        // a suburb that happens to be a state name is left alone
+        assert_eq!(
+            LocalityParts::parse("Victoria Point").suburb,
+            "Victoria Point"
+        );
+        assert_eq!(LocalityParts::parse("Victoria").suburb, "Victoria");
+        assert_eq!(
+            LocalityParts::parse("West End"),
+            LocalityParts {
+                suburb: "West End".to_string(),
+                state: None,
+                postcode: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_premises() {
+        let parse = |value: &str| {
+            let words = value.split_whitespace().collect::<Vec<_>>();
+            Premises::parse(&words).map(|(premises, used)| (premises.to_string(), used))
+        };
+        assert_eq!(parse("Unit 3, 12 Smith St"), Some(("3/12".to_string(), 3)));
+        assert_eq!(parse("apt. 4b 12 Smith St"), Some(("4B/12".to_string(), 3)));
+        assert_eq!(parse("3/12-14 Smith St"), Some(("3/12-14".to_string(), 1)));
+        assert_eq!(parse("Lot 5 Smith St"), Some(("Lot 5".to_string(), 2)));
+        assert_eq!(
+            parse("Lot 5 12 Smith St"),
+            Some(("Lot 5 12".to_string(), 3))
+        );
+        assert_eq!(parse("Unit 3 Smith St"), None);
+        assert_eq!(parse("Unit 3 4/12 Smith St"), None);
+        assert_eq!(parse("Smith St"), None);
+
+        let lot = |value: &str| Premises {
+            number: None,
+            lot: Some(value.to_string()),
+        };
+        assert_eq!(match_premises(&lot("5"), &lot("5")), NumberMatch::Exact);
+        assert_eq!(match_premises(&lot("5"), &lot("6")), NumberMatch::None);
+    }
+
+    #[test]
+    fn test_match_number() {
+        assert_eq!(match_number(&number("2"), &number("2")), NumberMatch::Exact);
+        assert_eq!(match_number(&number("2"), &number("12")), NumberMatch::None);
+        assert_eq!(
+            match_number(&number("2"), &number("2/22")),
+            NumberMatch::None
+        );
+        assert_eq!(match_number(&number("2"), &number("2A")), NumberMatch::None);
+        assert_eq!(
+            match_number(&number("12a"), &number("12A")),
+            NumberMatch::Exact
+        );
+        assert_eq!(
+            match_number(&number("14"), &number("12-14")),
+            NumberMatch::Range
+        );
+        // 13 is on the other side of the street
+        assert_eq!(
+            match_number(&number("13"), &number("12-14")),
+            NumberMatch::None
+        );
+        assert_eq!(
+            match_number(&number("13"), &number("11-15")),
+            NumberMatch::Range
+        );
+        assert_eq!(
+            match_number(&number("3"), &number("2-5")),
+            NumberMatch::Range
+        );
+        assert_eq!(
+            match_number(&number("12-14"), &number("12-14")),
+            NumberMatch::Exact
+        );
+        assert_eq!(
+            match_number(&number("15"), &number("12-14")),
+            NumberMatch::None
+        );
+        assert_eq!(
+            match_number(&number("22"), &number("2/22")),
+            NumberMatch::AnyUnit
+        );
+        assert_eq!(
+            match_number(&number("2/22"), &number("2/22")),
+            NumberMatch::Exact
+        );
+        assert_eq!(
+            match_number(&number("3/22"), &number("2/22")),
+            NumberMatch::None
+        );
+        assert_eq!(
+            match_number(&number("3/22"), &number("22")),
+            NumberMatch::None
+        );
+    }
+}
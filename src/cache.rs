@@ -0,0 +1,484 @@
+//! An on-disk cache for the API responses that hardly ever change, i.e. the locality, street and property lists,
+//! and the last bin data fetched for each property, for when the API can't be reached
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use time::{Date, Duration, OffsetDateTime};
+
+use crate::{BinData, BinDay};
+
+/// How long the list of localities is used before checking with the server again
+pub const LOCALITIES_TTL: Duration = Duration::days(30);
+/// How long a locality's list of streets is used before checking with the server again
+pub const STREETS_TTL: Duration = Duration::days(7);
+/// How long a street's list of properties is used before checking with the server again
+pub const PROPERTIES_TTL: Duration = Duration::days(7);
+
+/// The most days of bin data kept for a property, so the cached dates don't grow forever
+pub const MAX_SCHEDULE_DAYS: i64 = 366;
+
+/// The directory under the cache dir the responses are kept in
+const RESPONSES_DIR: &str = "responses";
+/// The directory under the cache dir the last known bin data for each property is kept in
+const SCHEDULES_DIR: &str = "schedules";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// When to use cached responses instead of asking the server
+pub enum OfflineMode {
+    /// Always ask the server, unless a cached list is still fresh
+    #[default]
+    Online,
+    /// Ask the server, but use whatever is cached if it can't be reached
+    Fallback,
+    /// Never ask the server, only use what is cached
+    Offline,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+/// A cached response
+pub struct CacheEntry {
+    /// When the response was fetched or last revalidated, as a unix timestamp
+    pub fetched: i64,
+    /// The ETag header, for If-None-Match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    /// The Last-Modified header, for If-Modified-Since
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+    /// The response body
+    pub body: String,
+}
+
+impl CacheEntry {
+    /// Was this fetched less than `ttl` before `now`?
+    pub fn is_fresh(&self, now: OffsetDateTime, ttl: Duration) -> bool {
+        now.unix_timestamp() - self.fetched < ttl.whole_seconds()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+/// A range of dates bin days were fetched for, and when
+pub struct FetchedRange {
+    /// When it was fetched, as a unix timestamp
+    pub fetched: i64,
+    /// The first date fetched
+    #[serde(with = "crate::ymd")]
+    pub start: Date,
+    /// The last date fetched
+    #[serde(with = "crate::ymd")]
+    pub end: Date,
+}
+
+impl FetchedRange {
+    /// Is `date` in this range?
+    pub fn contains(&self, date: Date) -> bool {
+        (self.start..=self.end).contains(&date)
+    }
+
+    /// The parts of this range that aren't in `other`, none, one or two of them
+    fn without(self, other: &FetchedRange) -> Vec<FetchedRange> {
+        if other.end < self.start || self.end < other.start {
+            return vec![self];
+        }
+        let before = other.start.previous_day().filter(|end| *end >= self.start);
+        let after = other.end.next_day().filter(|start| *start <= self.end);
+        before
+            .map(|end| FetchedRange { end, ..self })
+            .into_iter()
+            .chain(after.map(|start| FetchedRange { start, ..self }))
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+/// The last bin data fetched for a property
+pub struct CachedSchedule {
+    /// The ranges of dates the bin days were fetched for, in date order and not overlapping, each
+    /// with its own fetch time
+    pub ranges: Vec<FetchedRange>,
+    /// The property data, from the latest fetch
+    pub bin_data: BinData,
+    /// The bin days in the ranges
+    pub bin_days: Vec<BinDay>,
+}
+
+impl CachedSchedule {
+    /// The bin data fetched at `fetched` for the dates from `start` to `end`
+    pub fn new(
+        fetched: i64,
+        start: Date,
+        end: Date,
+        bin_data: BinData,
+        bin_days: Vec<BinDay>,
+    ) -> Self {
+        Self {
+            ranges: vec![FetchedRange {
+                fetched,
+                start,
+                end,
+            }],
+            bin_data,
+            bin_days,
+        }
+    }
+
+    /// The last date the bin days were fetched up to without a gap from `start`, or `None` if `start`
+    /// itself wasn't fetched
+    pub fn covered_until(&self, start: Date) -> Option<Date> {
+        let mut until: Option<Date> = None;
+        for range in &self.ranges {
+            match until {
+                None if range.contains(start) => until = Some(range.end),
+                Some(end) if end.next_day().is_some_and(|next| range.start <= next) => {
+                    until = Some(end.max(range.end));
+                }
+                Some(_) => break,
+                None => {}
+            }
+        }
+        until
+    }
+
+    /// Were the bin days fetched for every date from `start` to `end`?
+    pub fn covers(&self, start: Date, end: Date) -> bool {
+        self.covered_until(start).is_some_and(|until| until >= end)
+    }
+
+    /// When the oldest of the bin days from `start` to `end` were fetched, as a unix timestamp
+    pub fn fetched(&self, start: Date, end: Date) -> Option<i64> {
+        self.ranges
+            .iter()
+            .filter(|range| range.start <= end && start <= range.end)
+            .map(|range| range.fetched)
+            .min()
+    }
+
+    /// Add a newer fetch for the same property. Its ranges replace the dates they overlap, the rest keep
+    /// their own fetch times, and the ranges furthest from the newer ones are dropped to keep the whole
+    /// span within [MAX_SCHEDULE_DAYS].
+    pub fn merge(self, newer: CachedSchedule) -> CachedSchedule {
+        let mut ranges = self
+            .ranges
+            .into_iter()
+            .flat_map(|range| {
+                newer.ranges.iter().fold(vec![range], |pieces, fresh| {
+                    pieces
+                        .into_iter()
+                        .flat_map(|piece| piece.without(fresh))
+                        .collect()
+                })
+            })
+            .collect::<Vec<_>>();
+        ranges.extend(newer.ranges.iter().copied());
+        ranges.sort_by_key(|range| range.start);
+
+        let is_newer = |range: &FetchedRange| newer.ranges.contains(range);
+        let low = newer.ranges.iter().map(|range| range.start).min();
+        let high = newer.ranges.iter().map(|range| range.end).max();
+        if let (Some(low), Some(high)) = (low, high) {
+            while let (Some(first), Some(last)) = (ranges.first(), ranges.last()) {
+                if (last.end - first.start).whole_days() < MAX_SCHEDULE_DAYS {
+                    break;
+                }
+                let (first_newer, last_newer) = (is_newer(first), is_newer(last));
+                if !first_newer && (last_newer || low - first.start >= last.end - high) {
+                    ranges.remove(0);
+                } else if !last_newer {
+                    ranges.pop();
+                } else {
+                    break;
+                }
+            }
+        }
+
+        let in_newer = |day: &BinDay| newer.ranges.iter().any(|range| range.contains(day.start));
+        let kept = |day: &BinDay| ranges.iter().any(|range| range.contains(day.start));
+        let mut bin_days = self
+            .bin_days
+            .into_iter()
+            .filter(|day| !in_newer(day) && kept(day))
+            .chain(newer.bin_days)
+            .collect::<Vec<_>>();
+        bin_days.sort_by_key(|day| day.start);
+        CachedSchedule {
+            ranges,
+            bin_data: newer.bin_data,
+            bin_days,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The response cache, a directory of JSON files named after the request URLs
+pub struct Cache {
+    dir: PathBuf,
+    refresh: bool,
+}
+
+/// Turn a URL into a file name, keeping the letters, digits, dots and dashes
+fn file_name(key: &str) -> String {
+    let name = key
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    format!("{name}.json")
+}
+
+impl Cache {
+    /// A cache in `dir`, which is created when the first response is stored
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            refresh: false,
+        }
+    }
+
+    /// The cache in the XDG cache directory, `$XDG_CACHE_HOME/brisbane-bin-data` or
+    /// `$HOME/.cache/brisbane-bin-data`, or `None` if neither is set. `env` looks up a variable by name.
+    pub fn from_env(env: impl Fn(&str) -> Option<String>) -> Option<Self> {
+        let base = match env("XDG_CACHE_HOME").filter(|dir| Path::new(dir).is_absolute()) {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(env("HOME").filter(|home| !home.is_empty())?).join(".cache"),
+        };
+        Some(Self::new(base.join(env!("CARGO_PKG_NAME"))))
+    }
+
+    /// Builder method, ignore fresh entries and always check with the server, still storing the responses
+    pub fn with_refresh(self, refresh: bool) -> Self {
+        Self { refresh, ..self }
+    }
+
+    /// Should fresh entries be ignored?
+    pub fn refresh(&self) -> bool {
+        self.refresh
+    }
+
+    /// The directory the cache is in
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, dir: &str, key: &str) -> PathBuf {
+        self.dir.join(dir).join(file_name(key))
+    }
+
+    fn read<T: DeserializeOwned>(&self, dir: &str, key: &str) -> Option<T> {
+        let contents = fs::read(self.path(dir, key)).ok()?;
+        serde_json::from_slice(&contents).ok()
+    }
+
+    fn write(&self, dir: &str, key: &str, value: &impl Serialize) -> std::io::Result<()> {
+        let path = self.path(dir, key);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // write then rename, so a concurrent reader never sees half a file
+        let partial = path.with_extension("json.partial");
+        fs::write(&partial, serde_json::to_vec(value)?)?;
+        fs::rename(partial, path)
+    }
+
+    /// Load the entry for a URL, if there is one and it can be read
+    pub fn load(&self, key: &str) -> Option<CacheEntry> {
+        self.read(RESPONSES_DIR, key)
+    }
+
+    /// Store the entry for a URL
+    pub fn store(&self, key: &str, entry: &CacheEntry) -> std::io::Result<()> {
+        self.write(RESPONSES_DIR, key, entry)
+    }
+
+    /// Load the last bin data stored for a property, keyed by its URL
+    pub fn load_schedule(&self, key: &str) -> Option<CachedSchedule> {
+        self.read(SCHEDULES_DIR, key)
+    }
+
+    /// Store the bin data for a property, keyed by its URL
+    pub fn store_schedule(&self, key: &str, schedule: &CachedSchedule) -> std::io::Result<()> {
+        self.write(SCHEDULES_DIR, key, schedule)
+    }
+
+    /// Remove everything in the cache, returning how many files were removed
+    pub fn clear(&self) -> std::io::Result<usize> {
+        let mut removed = 0;
+        for dir in [RESPONSES_DIR, SCHEDULES_DIR] {
+            let entries = match fs::read_dir(self.dir.join(dir)) {
+                Ok(val) => val,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(err),
+            };
+            for entry in entries {
+                let path = entry?.path();
+                if path.is_file() {
+                    fs::remove_file(path)?;
+                    removed += 1;
+                }
+            }
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A cache in a fresh directory under the system temp dir
+    pub(crate) fn temp_cache(name: &str) -> Cache {
+        let dir = std::env::temp_dir().join(format!(
+            "{}-test-{}-{name}",
+            env!("CARGO_PKG_NAME"),
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        Cache::new(dir)
+    }
+
+    #[test]
+    fn test_from_env() {
+        let cache = Cache::from_env(|name| match name {
+            "XDG_CACHE_HOME" => Some("/var/cache/me".to_string()),
+            _ => None,
+        })
+        .expect("Cache should be found");
+        assert_eq!(cache.dir(), Path::new("/var/cache/me/brisbane-bin-data"));
+
+        let cache = Cache::from_env(|name| match name {
+            "XDG_CACHE_HOME" => Some("relative".to_string()),
+            "HOME" => Some("/home/me".to_string()),
+            _ => None,
+        })
+        .expect("Cache should be found");
+        assert_eq!(cache.dir(), Path::new("/home/me/.cache/brisbane-bin-data"));
+
+        assert!(Cache::from_env(|_| None).is_none());
+    }
+
+    #[test]
+    fn test_store_load_clear() {
+        let cache = temp_cache("store");
+        let key = "https://brisbane.waste-info.com.au/api/v1/streets.json?locality=2";
+        assert!(cache.load(key).is_none());
+        assert_eq!(cache.clear().expect("Failed to clear empty cache"), 0);
+
+        let entry = CacheEntry {
+            fetched: 1_700_000_000,
+            etag: Some("\"v1\"".to_string()),
+            last_modified: None,
+            body: "{\"streets\": []}".to_string(),
+        };
+        cache.store(key, &entry).expect("Failed to store");
+        assert_eq!(cache.load(key), Some(entry.clone()));
+        assert!(cache.load("https://example.com/other").is_none());
+
+        let fetched = OffsetDateTime::from_unix_timestamp(entry.fetched).expect("valid timestamp");
+        assert!(entry.is_fresh(fetched + Duration::days(6), STREETS_TTL));
+        assert!(!entry.is_fresh(fetched + Duration::days(7), STREETS_TTL));
+
+        assert_eq!(cache.clear().expect("Failed to clear"), 1);
+        assert!(cache.load(key).is_none());
+        let _ = fs::remove_dir_all(cache.dir());
+    }
+
+    #[test]
+    fn test_merge_schedules() {
+        let date = |month: time::Month, day: u8| {
+            Date::from_calendar_date(2025, month, day).expect("valid date")
+        };
+        let (jan, feb, mar) = (
+            time::Month::January,
+            time::Month::February,
+            time::Month::March,
+        );
+        let schedule = |fetched: i64, start: Date, end: Date, days: &[&str]| {
+            CachedSchedule::new(
+                fetched,
+                start,
+                end,
+                crate::fixtures::bin_data(),
+                days.iter()
+                    .map(|day| crate::fixtures::recycling(day))
+                    .collect(),
+            )
+        };
+        let starts = |schedule: &CachedSchedule| {
+            schedule
+                .bin_days
+                .iter()
+                .map(|day| day.start.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        // each merge keeps the fetch time of the dates it didn't refetch
+        let merged = schedule(
+            100,
+            date(jan, 1),
+            date(jan, 31),
+            &["2025-01-07", "2025-01-21"],
+        )
+        .merge(schedule(200, date(jan, 15), date(feb, 14), &["2025-02-04"]))
+        .merge(schedule(
+            300,
+            date(feb, 1),
+            date(feb, 28),
+            &["2025-02-04", "2025-02-18"],
+        ))
+        .merge(schedule(400, date(feb, 20), date(mar, 20), &["2025-03-04"]));
+        assert_eq!(
+            merged
+                .ranges
+                .iter()
+                .map(|range| (range.start, range.end, range.fetched))
+                .collect::<Vec<_>>(),
+            vec![
+                (date(jan, 1), date(jan, 14), 100),
+                (date(jan, 15), date(jan, 31), 200),
+                (date(feb, 1), date(feb, 19), 300),
+                (date(feb, 20), date(mar, 20), 400),
+            ]
+        );
+        assert_eq!(
+            starts(&merged),
+            vec!["2025-01-07", "2025-02-04", "2025-02-18", "2025-03-04"]
+        );
+        assert_eq!(merged.fetched(date(mar, 1), date(mar, 20)), Some(400));
+        assert_eq!(merged.fetched(date(feb, 10), date(mar, 20)), Some(300));
+        assert_eq!(merged.fetched(date(jan, 1), date(mar, 20)), Some(100));
+        assert!(merged.covers(date(jan, 10), date(mar, 20)));
+        assert_eq!(merged.covered_until(date(feb, 25)), Some(date(mar, 20)));
+        assert!(!merged.covers(date(jan, 10), date(mar, 21)));
+
+        // a gap stops the coverage
+        let gappy = schedule(100, date(jan, 1), date(jan, 10), &[]).merge(schedule(
+            200,
+            date(jan, 20),
+            date(jan, 31),
+            &[],
+        ));
+        assert_eq!(gappy.covered_until(date(jan, 5)), Some(date(jan, 10)));
+        assert_eq!(gappy.covered_until(date(jan, 15)), None);
+
+        // merging a month at a time for years only keeps about a year
+        let mut merged = schedule(0, date(jan, 1), date(jan, 31), &["2025-01-07"]);
+        let mut start = date(feb, 1);
+        for fetched in 1..40 {
+            let (first, last) = crate::month_window(start).expect("valid month");
+            merged = merged.merge(schedule(fetched, first, last, &[]));
+            start = last.next_day().expect("valid date");
+        }
+        let (first, last) = (merged.ranges[0], merged.ranges[merged.ranges.len() - 1]);
+        assert!((last.end - first.start).whole_days() < MAX_SCHEDULE_DAYS);
+        assert_eq!(last.fetched, 39);
+        assert!(merged.bin_days.is_empty());
+    }
+}
@@ -0,0 +1,243 @@
+//! A month calendar grid for the terminal, with each bin night marked on it
+
+use std::{collections::BTreeMap, io::Write};
+
+use time::{util::days_in_month, Date, Weekday};
+
+use crate::{
+    month_window,
+    render::Renderer,
+    schedule::{CollectionSource, Schedule},
+    terminal::ColorDepth,
+    BinError, BinKind, Rgb, ScheduleView,
+};
+
+/// The column headings, the grid starts on a Monday
+const WEEKDAYS: [(Weekday, &str); 7] = [
+    (Weekday::Monday, "Mo"),
+    (Weekday::Tuesday, "Tu"),
+    (Weekday::Wednesday, "We"),
+    (Weekday::Thursday, "Th"),
+    (Weekday::Friday, "Fr"),
+    (Weekday::Saturday, "Sa"),
+    (Weekday::Sunday, "Su"),
+];
+
+/// How many glyphs fit in a day's cell
+const GLYPHS_PER_DAY: usize = 3;
+
+/// Explains the glyphs and markers, written after the last month
+const LEGEND: &str =
+    "G general  R recycling  W green waste  K kerbside clean-up  ? other  (lowercase if predicted)\n\
+     [ ] today  * collection day";
+
+/// The single character used for a kind on the grid, lowercase if it's predicted
+fn glyph(kind: &BinKind, source: CollectionSource) -> char {
+    let glyph = match kind {
+        BinKind::General => 'G',
+        BinKind::Recycling => 'R',
+        BinKind::GreenWaste => 'W',
+        BinKind::KerbsideCleanup => 'K',
+        BinKind::Unknown { .. } => '?',
+    };
+    match source {
+        CollectionSource::Confirmed => glyph,
+        CollectionSource::Predicted => glyph.to_ascii_lowercase(),
+    }
+}
+
+/// The first and last dates of the `months` months starting with the month `start` is in
+pub fn calendar_range(start: Date, months: u32) -> Result<(Date, Date), BinError> {
+    let (first, mut end) = month_window(start)?;
+    for _ in 1..months.max(1) {
+        let next = end
+            .next_day()
+            .ok_or(BinError::InvalidDateRange { start, end })?;
+        end = month_window(next)?.1;
+    }
+    Ok((first, end))
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Writes a grid for each month, Monday to Sunday, with a glyph for each bin collected on a day
+pub struct CalendarRenderer {
+    /// A date in the first month to show
+    pub start: Date,
+    /// How many months to show
+    pub months: u32,
+    /// How to colour the glyphs and highlights
+    pub color: ColorDepth,
+}
+
+/// The bins collected on a day, with their colours and whether they were listed or predicted
+type Collections = BTreeMap<Date, Vec<(BinKind, Rgb, CollectionSource)>>;
+
+impl CalendarRenderer {
+    /// The bins collected on each day from `first` to `last`, projected from the property's [Schedule]
+    fn collections(
+        view: &ScheduleView<'_>,
+        schedule: &Schedule,
+        first: Date,
+        last: Date,
+    ) -> Collections {
+        let weeks = u32::try_from(((last - first).whole_days() + 7) / 7).unwrap_or_default();
+        let general = view.bin_data.kind();
+        let mut collections = Collections::new();
+        for item in schedule.project(view.all_days, first, weeks) {
+            let colour = item.colour(view.bin_data, view.all_days).clone();
+            let bins = collections.entry(item.date).or_default();
+            if bins.iter().any(|(kind, _, _)| *kind == item.kind) {
+                continue;
+            }
+            bins.push((item.kind, colour, item.source));
+            // the regular pickup first
+            bins.sort_by_key(|(kind, _, _)| *kind != general);
+        }
+        collections
+    }
+
+    /// The weekdays of the property's regular pickup
+    fn collection_weekdays(view: &ScheduleView<'_>, schedule: &Schedule) -> Vec<Weekday> {
+        let general = view.bin_data.kind();
+        schedule
+            .rules
+            .iter()
+            .filter(|rule| rule.kind == general && rule.interval_weeks == 1)
+            .map(|rule| rule.anchor.weekday())
+            .collect()
+    }
+
+    /// Write the grid for the month starting on `first`
+    fn write_month(
+        &self,
+        writer: &mut dyn Write,
+        view: &ScheduleView<'_>,
+        first: Date,
+        weekdays: &[Weekday],
+        collections: &Collections,
+    ) -> std::io::Result<()> {
+        let title = format!("{:^49}", format!("{} {}", first.month(), first.year()));
+        writeln!(writer, "{}", title.trim_end())?;
+
+        let header = WEEKDAYS
+            .iter()
+            .map(|(weekday, label)| {
+                if weekdays.contains(weekday) {
+                    format!(" {}   ", self.color.highlight(&format!("{label}*")))
+                } else {
+                    format!(" {label}    ")
+                }
+            })
+            .collect::<String>();
+        writeln!(writer, "{}", header.trim_end())?;
+
+        let mut row = "       ".repeat(usize::from(first.weekday().number_days_from_monday()));
+        let days = days_in_month(first.month(), first.year());
+        for day in 1..=days {
+            let Ok(date) = first.replace_day(day) else {
+                continue;
+            };
+            let number = if date == view.today {
+                self.color.highlight(&format!("[{day:>2}]"))
+            } else {
+                format!(" {day:>2} ")
+            };
+            let bins = collections.get(&date).map_or(&[][..], Vec::as_slice);
+            let glyphs = bins
+                .iter()
+                .take(GLYPHS_PER_DAY)
+                .map(|(kind, colour, source)| {
+                    self.color.paint(&glyph(kind, *source).to_string(), colour)
+                })
+                .collect::<String>();
+            let padding = " ".repeat(GLYPHS_PER_DAY - bins.len().min(GLYPHS_PER_DAY));
+            row.push_str(&format!("{number}{glyphs}{padding}"));
+
+            if date.weekday() == Weekday::Sunday || day == days {
+                writeln!(writer, "{}", row.trim_end())?;
+                row.clear();
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Renderer for CalendarRenderer {
+    fn render(&self, view: &ScheduleView<'_>, writer: &mut dyn Write) -> std::io::Result<()> {
+        let (first, last) = calendar_range(self.start, self.months)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+        let schedule = Schedule::infer(view.bin_data, view.all_days, first);
+        let weekdays = Self::collection_weekdays(view, &schedule);
+        let collections = Self::collections(view, &schedule, first, last);
+
+        writeln!(writer, "{}", view.bin_data.property)?;
+        let mut month = first;
+        while month <= last {
+            writeln!(writer)?;
+            self.write_month(writer, view, month, &weekdays, &collections)?;
+            month = match month_window(month).map(|(_, end)| end.next_day()) {
+                Ok(Some(val)) => val,
+                _ => break,
+            };
+        }
+        writeln!(writer)?;
+        writeln!(writer, "{LEGEND}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn test_calendar_range() {
+        let start = Date::from_calendar_date(2025, time::Month::November, 17).expect("valid date");
+        let (first, last) = calendar_range(start, 3).expect("Failed to get range");
+        assert_eq!(first.to_string(), "2025-11-01");
+        assert_eq!(last.to_string(), "2026-01-31");
+
+        let (first, last) = calendar_range(start, 0).expect("Failed to get range");
+        assert_eq!(first.to_string(), "2025-11-01");
+        assert_eq!(last.to_string(), "2025-11-30");
+    }
+
+    #[test]
+    fn test_calendar_grid() {
+        let bin_data = fixtures::bin_data();
+        let bin_days = vec![fixtures::recycling("2025-10-14")];
+        let today = Date::from_calendar_date(2025, time::Month::October, 17).expect("valid date");
+        let view = ScheduleView::new(&bin_data, &bin_days, today, false);
+
+        let mut buf = Vec::new();
+        CalendarRenderer {
+            start: today,
+            months: 2,
+            color: ColorDepth::None,
+        }
+        .render(&view, &mut buf)
+        .expect("Failed to render");
+        let calendar = String::from_utf8(buf).expect("valid utf8");
+        let lines = calendar.lines().collect::<Vec<_>>();
+
+        let october = lines
+            .iter()
+            .position(|line| line.trim() == "October 2025")
+            .expect("October should be shown");
+        assert_eq!(
+            lines[october + 1],
+            " Mo     Tu*    We     Th     Fr     Sa     Su"
+        );
+        // October 2025 starts on a Wednesday
+        assert_eq!(
+            lines[october + 2],
+            "                1      2      3      4      5"
+        );
+        // the listed recycling is uppercase, the weekly pickup and the next recycling are predicted
+        assert!(lines.contains(&" 13     14 gR  15     16    [17]    18     19"));
+        assert!(lines.contains(&" 27     28 gr  29     30     31"));
+        assert!(calendar.contains("November 2025"));
+        assert!(lines.contains(&" 24     25 gr  26     27     28     29     30"));
+        assert!(calendar.ends_with(&format!("{LEGEND}\n")));
+    }
+}
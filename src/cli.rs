@@ -1,7 +1,14 @@
 //! Cli Interface
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use time::{
+    format_description::{self, well_known::Iso8601},
+    Date, Duration, Time, UtcOffset,
+};
 
-use crate::AddressData;
+use crate::{
+    cache::OfflineMode, rate_limit::RateLimit, render::OutputFormat, retry::RetryPolicy,
+    terminal::ColorChoice, AddressData, BinError, Council,
+};
 
 #[derive(Parser)]
 #[command(version, about)]
@@ -12,28 +19,304 @@ pub struct Cli {
     #[clap(long)]
     /// Enable debug logging
     pub debug: bool,
+    /// The council to query, only brisbane is built in. Any other council on the waste-info.com.au platform
+    /// can be queried by giving its name along with its --base-url, e.g. --council "Logan City Council"
+    #[clap(long, env = "BIN_DATA_COUNCIL", default_value = "brisbane")]
+    pub council: String,
+    /// Query a different API server, e.g. a local stub or another council on the same platform
+    #[clap(long, env = "BIN_DATA_BASE_URL")]
+    pub base_url: Option<String>,
+    /// The council's UTC offset, e.g. +09:30, used to work out what "today" is (defaults to +10:00)
+    #[clap(long, env = "BIN_DATA_TIMEZONE", value_parser = parse_offset, allow_hyphen_values = true)]
+    pub timezone: Option<UtcOffset>,
+    /// How many times to retry a request that timed out or got a 429 or 5xx response, waiting longer each time
+    #[clap(long, env = "BIN_DATA_RETRIES", default_value_t = 2)]
+    pub retries: u32,
+    /// How many seconds to wait for each attempt at a request before giving up on it
+    #[clap(long, env = "BIN_DATA_TIMEOUT", default_value_t = 30, value_parser = clap::value_parser!(u64).range(1..))]
+    pub timeout: u64,
+    /// The most requests to send per second, to go easy on the council's server when looking up lots of
+    /// properties
+    #[clap(long, env = "BIN_DATA_RATE_LIMIT", default_value_t = 5.0, value_parser = parse_rate)]
+    pub rate_limit: f64,
+    /// The most requests to have waiting for a response at once
+    #[clap(long, default_value_t = 4)]
+    pub max_concurrency: usize,
+    /// Don't ask which suburb, street or property you meant when the address doesn't match exactly,
+    /// which is the default when input isn't a terminal
+    #[clap(long)]
+    pub no_input: bool,
+    /// Don't read or write the cache of locality, street and property lists
+    #[clap(long, conflicts_with = "refresh")]
+    pub no_cache: bool,
+    /// Check the cached locality, street and property lists with the server, even if they're fresh
+    #[clap(long)]
+    pub refresh: bool,
+    /// Don't ask the server at all, use the cached lists and the last bin data fetched for the property
+    #[clap(long, conflicts_with_all = ["refresh", "no_cache", "offline_fallback"])]
+    pub offline: bool,
+    /// Use the cached lists and the last bin data fetched for the property if the server can't be reached
+    #[clap(long, conflicts_with = "no_cache")]
+    pub offline_fallback: bool,
     /// Just go straight to the data if you know your property ID (it's in the 'full' output)
     #[clap(long, short)]
     pub property_id: Option<u64>,
 
-    /// Just show my bin day(s), might return a list if you're lucky enough to have more than one!
-    #[clap(long, short)]
+    /// Just show my bin day(s), might return a list if you're lucky enough to have more than one! Same as --format days
+    #[clap(long, short, conflicts_with_all = ["format", "pretty"])]
     pub show_day: bool,
     /// Just show future dates
     #[clap(long, short)]
     pub future: bool,
-    /// Show pretty data instead of JSON
-    #[clap(long, short = 'P')]
+    /// Show pretty data instead of JSON, same as --format pretty
+    #[clap(long, short = 'P', conflicts_with = "format")]
     pub pretty: bool,
+    /// The output format
+    #[clap(long, value_enum, default_value_t = OutputFormat::Json)]
+    pub format: OutputFormat,
+    /// When to colour the pretty output, honours NO_COLOR in auto mode
+    #[clap(long, value_enum, default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+    /// With --format ics, add a reminder at this time (HH:MM) the evening before each collection
+    #[clap(long, value_parser = parse_time)]
+    pub alarm: Option<Time>,
+
+    /// The first date to show, in the format YYYY-MM-DD (defaults to today if --to or --days is set)
+    #[clap(long, value_parser = parse_date)]
+    pub from: Option<Date>,
+    /// The last date to show, in the format YYYY-MM-DD
+    #[clap(long, value_parser = parse_date, conflicts_with = "days")]
+    pub to: Option<Date>,
+    /// Show this many days, starting from --from (or today)
+    #[clap(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub days: Option<u32>,
+
+    #[command(subcommand)]
+    /// What to do, shows the bin data if not set
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug, PartialEq, Eq)]
+/// Things to do other than showing the bin data
+pub enum Command {
+    /// Show the next collection date and which bins go out, e.g. "Tuesday 21 Oct: general + recycling"
+    Next,
+    /// Show a month calendar with the bin nights marked, starting from --from (or this month)
+    Calendar {
+        /// How many months to show, up to two years
+        #[clap(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..=24))]
+        months: u32,
+    },
+    /// List the localities (suburbs) for the council, to find the name the API uses
+    Localities {
+        /// Only show localities with this in their name
+        #[clap(long)]
+        filter: Option<String>,
+    },
+    /// List the streets in a locality, e.g. "West End"
+    Streets {
+        /// The locality to list the streets in
+        suburb: String,
+        /// Only show streets with this in their name
+        #[clap(long)]
+        filter: Option<String>,
+    },
+    /// List the properties on a street, with their IDs for --property-id
+    Properties {
+        /// The street and locality, in the format "drury lane, suburb"
+        #[clap(value_parser = parse_street)]
+        street: (String, String),
+        /// Only show properties with this in their name, e.g. a house number
+        #[clap(long)]
+        filter: Option<String>,
+    },
+    /// Manage the cache of locality, street and property lists
+    Cache {
+        #[command(subcommand)]
+        /// What to do with the cache
+        action: CacheAction,
+    },
+}
+
+#[derive(Subcommand, Debug, PartialEq, Eq)]
+/// Things to do with the cache
+pub enum CacheAction {
+    /// Remove everything in the cache
+    Clear,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// One of the listing commands, which don't need an address, see [Command::listing]
+pub enum ListCommand<'a> {
+    /// List the council's localities
+    Localities {
+        /// Only show localities with this in their name
+        filter: Option<&'a str>,
+    },
+    /// List the streets in a locality
+    Streets {
+        /// The locality to list the streets in
+        suburb: &'a str,
+        /// Only show streets with this in their name
+        filter: Option<&'a str>,
+    },
+    /// List the properties on a street
+    Properties {
+        /// The street name
+        street: &'a str,
+        /// The street's locality
+        suburb: &'a str,
+        /// Only show properties with this in their name
+        filter: Option<&'a str>,
+    },
+}
+
+impl Command {
+    /// The listing this command asks for, or `None` if it isn't one of the listing commands
+    pub fn listing(&self) -> Option<ListCommand<'_>> {
+        match self {
+            Command::Localities { filter } => Some(ListCommand::Localities {
+                filter: filter.as_deref(),
+            }),
+            Command::Streets { suburb, filter } => Some(ListCommand::Streets {
+                suburb,
+                filter: filter.as_deref(),
+            }),
+            Command::Properties {
+                street: (street, suburb),
+                filter,
+            } => Some(ListCommand::Properties {
+                street,
+                suburb,
+                filter: filter.as_deref(),
+            }),
+            Command::Next | Command::Calendar { .. } | Command::Cache { .. } => None,
+        }
+    }
+}
+
+/// Parse a street and locality in the format "drury lane, suburb"
+fn parse_street(value: &str) -> Result<(String, String), BinError> {
+    match value.rsplit_once(',') {
+        Some((street, suburb)) if !street.trim().is_empty() && !suburb.trim().is_empty() => {
+            Ok((street.trim().to_string(), suburb.trim().to_string()))
+        }
+        _ => Err(BinError::InvalidAddress(format!(
+            "expected \"street, suburb\", got \"{value}\""
+        ))),
+    }
+}
+
+/// Parse a time in the format HH:MM
+fn parse_time(value: &str) -> Result<Time, BinError> {
+    let formatter = format_description::parse_borrowed::<2>("[hour]:[minute]")
+        .map_err(|err| BinError::date("generate time formatter", err))?;
+    Time::parse(value, &formatter).map_err(|err| BinError::date("parse time", err))
+}
+
+/// Parse a UTC offset in the format +HH:MM
+fn parse_offset(value: &str) -> Result<UtcOffset, BinError> {
+    let formatter =
+        format_description::parse_borrowed::<2>("[offset_hour sign:mandatory]:[offset_minute]")
+            .map_err(|err| BinError::date("generate offset formatter", err))?;
+    UtcOffset::parse(value, &formatter).map_err(|err| BinError::date("parse UTC offset", err))
+}
+
+/// Parse a request rate, which has to be a number of requests per second above zero
+fn parse_rate(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(rate) if rate.is_finite() && rate > 0.0 => Ok(rate),
+        _ => Err(format!(
+            "expected a number of requests per second above zero, got \"{value}\""
+        )),
+    }
+}
+
+/// Parse a date in the format YYYY-MM-DD
+fn parse_date(value: &str) -> Result<Date, BinError> {
+    Date::parse(value, &Iso8601::DATE).map_err(|err| BinError::date("parse date", err))
 }
 
 impl Cli {
+    /// The council to query, from --council, --base-url and --timezone. A council that isn't built in
+    /// needs a --base-url to be queried.
+    pub fn council(&self) -> Result<Council, BinError> {
+        let council = match (self.council.parse::<Council>(), &self.base_url) {
+            (Ok(council), _) => council,
+            (Err(_), Some(base_url)) => Council::custom(&self.council, base_url),
+            (Err(err), None) => return Err(err),
+        };
+        Ok(match self.timezone {
+            Some(timezone) => council.with_timezone(timezone),
+            None => council,
+        })
+    }
+
+    /// How to retry failed requests, from --retries and --timeout
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: self.retries.saturating_add(1),
+            timeout: std::time::Duration::from_secs(self.timeout),
+            ..RetryPolicy::default()
+        }
+    }
+
+    /// How fast to send requests, from --rate-limit and --max-concurrency
+    pub fn rate_limit(&self) -> RateLimit {
+        RateLimit {
+            requests_per_second: self.rate_limit,
+            max_concurrency: self.max_concurrency,
+            ..RateLimit::default()
+        }
+    }
+
+    /// When to use cached data instead of asking the server, from --offline and --offline-fallback
+    pub fn offline_mode(&self) -> OfflineMode {
+        if self.offline {
+            OfflineMode::Offline
+        } else if self.offline_fallback {
+            OfflineMode::Fallback
+        } else {
+            OfflineMode::Online
+        }
+    }
+
+    /// The output format, taking the --show-day and --pretty shortcuts into account
+    pub fn output_format(&self) -> OutputFormat {
+        if self.show_day {
+            OutputFormat::Days
+        } else if self.pretty {
+            OutputFormat::Pretty
+        } else {
+            self.format
+        }
+    }
+
+    /// Get the range of dates asked for with --from, --to and --days, if any of them were set
+    pub fn get_date_range(&self, today: Date) -> Result<Option<(Date, Date)>, BinError> {
+        if self.from.is_none() && self.to.is_none() && self.days.is_none() {
+            return Ok(None);
+        }
+        let start = self.from.unwrap_or(today);
+        let end = match (self.to, self.days) {
+            (Some(end), _) => end,
+            (None, Some(days)) => start
+                .checked_add(Duration::days(i64::from(days) - 1))
+                .ok_or(BinError::InvalidDateRange { start, end: start })?,
+            (None, None) => crate::month_window(start)?.1,
+        };
+        if start > end {
+            return Err(BinError::InvalidDateRange { start, end });
+        }
+        Ok(Some((start, end)))
+    }
+
     /// Get the parsed address data
-    pub fn get_data(&self) -> Result<AddressData, String> {
+    pub fn get_data(&self) -> Result<AddressData, BinError> {
         if let Some(address) = &self.address {
             AddressData::try_from(address.clone())
         } else {
-            Err("No address provided".to_string())
+            Err(BinError::InvalidAddress("No address provided".to_string()))
         }
     }
 }
@@ -59,4 +342,213 @@ mod tests {
         let testval = Cli::try_parse_from(["test"]).expect("Failed to parse CLI");
         assert!(testval.get_data().is_err());
     }
+
+    #[test]
+    fn test_rate_limit_args() {
+        let testval =
+            Cli::try_parse_from(["test", "--rate-limit", "0.5", "--max-concurrency", "1"])
+                .expect("Failed to parse CLI");
+        let limit = testval.rate_limit();
+        assert_eq!(limit.requests_per_second, 0.5);
+        assert_eq!(limit.max_concurrency, 1);
+
+        for rate in ["0", "-1", "NaN", "inf", "fast"] {
+            assert!(
+                Cli::try_parse_from(["test", "--rate-limit", rate]).is_err(),
+                "--rate-limit {rate} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_council_args() {
+        let testval = Cli::try_parse_from(["test"]).expect("Failed to parse CLI");
+        assert_eq!(
+            testval.council().expect("Failed to get council"),
+            Council::brisbane()
+        );
+
+        let testval = Cli::try_parse_from([
+            "test",
+            "--council",
+            "Logan City Council",
+            "--base-url",
+            "https://logan.waste-info.com.au/api/v1/",
+            "--timezone",
+            "+09:30",
+        ])
+        .expect("Failed to parse CLI");
+        let council = testval.council().expect("Failed to get council");
+        assert_eq!(council.key, "logan-city-council");
+        assert_eq!(council.base_url, "https://logan.waste-info.com.au/api/v1/");
+        assert_eq!(
+            council.timezone,
+            UtcOffset::from_hms(9, 30, 0).expect("valid offset")
+        );
+
+        // an unknown council can't be queried without knowing where its API is
+        let testval =
+            Cli::try_parse_from(["test", "--council", "Gotham"]).expect("Failed to parse CLI");
+        assert!(matches!(
+            testval.council(),
+            Err(BinError::UnknownCouncil(_))
+        ));
+        assert!(Cli::try_parse_from(["test", "--timezone", "AEST"]).is_err());
+    }
+
+    #[test]
+    fn test_retry_args() {
+        let testval = Cli::try_parse_from(["test"]).expect("Failed to parse CLI");
+        assert_eq!(testval.retry_policy().max_attempts, 3);
+
+        let testval = Cli::try_parse_from(["test", "--retries", "0"]).expect("Failed to parse CLI");
+        assert_eq!(testval.retry_policy(), RetryPolicy::none());
+
+        let testval = Cli::try_parse_from(["test", "--timeout", "5"]).expect("Failed to parse CLI");
+        assert_eq!(
+            testval.retry_policy().timeout,
+            std::time::Duration::from_secs(5)
+        );
+        assert!(Cli::try_parse_from(["test", "--timeout", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_ics_format() {
+        let testval = Cli::try_parse_from(["test", "--format", "ics", "--alarm", "18:30"])
+            .expect("Failed to parse CLI");
+        assert_eq!(testval.output_format(), OutputFormat::Ics);
+        assert_eq!(
+            testval.alarm,
+            Some(Time::from_hms(18, 30, 0).expect("valid time"))
+        );
+        assert!(Cli::try_parse_from(["test", "--alarm", "6pm"]).is_err());
+
+        let testval = Cli::try_parse_from(["test", "-P"]).expect("Failed to parse CLI");
+        assert_eq!(testval.output_format(), OutputFormat::Pretty);
+        let testval = Cli::try_parse_from(["test", "-s"]).expect("Failed to parse CLI");
+        assert_eq!(testval.output_format(), OutputFormat::Days);
+        assert!(Cli::try_parse_from(["test", "-P", "--format", "csv"]).is_err());
+    }
+
+    #[test]
+    fn test_next_command() {
+        let testval = Cli::try_parse_from(["test", "123 drury lane, suburb", "next"])
+            .expect("Failed to parse CLI");
+        assert_eq!(testval.command, Some(Command::Next));
+        assert!(testval.get_data().is_ok());
+
+        let testval =
+            Cli::try_parse_from(["test", "-p", "1234", "next"]).expect("Failed to parse CLI");
+        assert_eq!(testval.command, Some(Command::Next));
+        assert_eq!(testval.property_id, Some(1234));
+    }
+
+    #[test]
+    fn test_listing_commands() {
+        let testval = Cli::try_parse_from(["test", "localities", "--filter", "west"])
+            .expect("Failed to parse CLI");
+        assert_eq!(
+            testval.command,
+            Some(Command::Localities {
+                filter: Some("west".to_string())
+            })
+        );
+
+        let testval = Cli::try_parse_from(["test", "-P", "properties", "Boundary St, West End"])
+            .expect("Failed to parse CLI");
+        assert_eq!(
+            testval.command,
+            Some(Command::Properties {
+                street: ("Boundary St".to_string(), "West End".to_string()),
+                filter: None,
+            })
+        );
+        assert_eq!(
+            testval.command.as_ref().and_then(Command::listing),
+            Some(ListCommand::Properties {
+                street: "Boundary St",
+                suburb: "West End",
+                filter: None,
+            })
+        );
+        assert_eq!(Command::Next.listing(), None);
+        assert!(Cli::try_parse_from(["test", "properties", "Boundary St"]).is_err());
+        assert!(Cli::try_parse_from(["test", "streets"]).is_err());
+    }
+
+    #[test]
+    fn test_cache_flags() {
+        let testval = Cli::try_parse_from(["test", "cache", "clear"]).expect("Failed to parse CLI");
+        assert_eq!(
+            testval.command,
+            Some(Command::Cache {
+                action: CacheAction::Clear
+            })
+        );
+        assert!(Cli::try_parse_from(["test", "cache"]).is_err());
+
+        let testval =
+            Cli::try_parse_from(["test", "--refresh", "-p", "1234"]).expect("Failed to parse CLI");
+        assert!(testval.refresh);
+        assert!(!testval.no_cache);
+        assert!(Cli::try_parse_from(["test", "--refresh", "--no-cache"]).is_err());
+
+        let testval =
+            Cli::try_parse_from(["test", "--offline", "-p", "1234"]).expect("Failed to parse CLI");
+        assert!(testval.offline);
+        assert!(!testval.offline_fallback);
+        assert!(Cli::try_parse_from(["test", "--offline", "--refresh"]).is_err());
+        assert!(Cli::try_parse_from(["test", "--offline", "--offline-fallback"]).is_err());
+        assert!(Cli::try_parse_from(["test", "--offline-fallback", "--no-cache"]).is_err());
+    }
+
+    #[test]
+    fn test_calendar_command() {
+        let testval =
+            Cli::try_parse_from(["test", "-p", "1234", "calendar"]).expect("Failed to parse CLI");
+        assert_eq!(testval.command, Some(Command::Calendar { months: 1 }));
+
+        let testval = Cli::try_parse_from(["test", "-p", "1234", "calendar", "--months", "3"])
+            .expect("Failed to parse CLI");
+        assert_eq!(testval.command, Some(Command::Calendar { months: 3 }));
+        assert!(Cli::try_parse_from(["test", "calendar", "--months", "many"]).is_err());
+        assert!(Cli::try_parse_from(["test", "calendar", "--months", "0"]).is_err());
+        assert!(Cli::try_parse_from(["test", "calendar", "--months", "25"]).is_err());
+    }
+
+    #[test]
+    fn test_date_range() {
+        let today = Date::from_calendar_date(2024, time::Month::October, 17).expect("valid date");
+
+        let testval = Cli::try_parse_from(["test"]).expect("Failed to parse CLI");
+        assert!(testval
+            .get_date_range(today)
+            .expect("Failed to get range")
+            .is_none());
+
+        let testval = Cli::try_parse_from(["test", "--days", "7"]).expect("Failed to parse CLI");
+        let (start, end) = testval
+            .get_date_range(today)
+            .expect("Failed to get range")
+            .expect("Range should be set");
+        assert_eq!(start, today);
+        assert_eq!(end.to_string(), "2024-10-23");
+
+        let testval = Cli::try_parse_from(["test", "--from", "2024-11-01", "--to", "2024-12-31"])
+            .expect("Failed to parse CLI");
+        let (start, end) = testval
+            .get_date_range(today)
+            .expect("Failed to get range")
+            .expect("Range should be set");
+        assert_eq!(start.to_string(), "2024-11-01");
+        assert_eq!(end.to_string(), "2024-12-31");
+
+        let testval =
+            Cli::try_parse_from(["test", "--to", "2024-10-01"]).expect("Failed to parse CLI");
+        assert!(testval.get_date_range(today).is_err());
+
+        assert!(Cli::try_parse_from(["test", "--to", "2024-10-01", "--days", "3"]).is_err());
+        assert!(Cli::try_parse_from(["test", "--days", "0"]).is_err());
+        assert!(Cli::try_parse_from(["test", "--from", "not-a-date"]).is_err());
+    }
 }
@@ -0,0 +1,52 @@
+//! Sources of the current time, either the system clock or a fixed time
+
+use time::{Date, OffsetDateTime, UtcOffset};
+
+/// Something that can tell the time
+pub trait Clock: Send + Sync {
+    /// The current time
+    fn now(&self) -> OffsetDateTime;
+
+    /// Today's date in the given timezone
+    fn today(&self, timezone: UtcOffset) -> Date {
+        self.now().to_offset(timezone).date()
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+/// The system clock, used by default
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> OffsetDateTime {
+        OffsetDateTime::now_utc()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+/// A clock that's stuck at a given time, for testing
+pub struct FixedClock(pub OffsetDateTime);
+
+impl Clock for FixedClock {
+    fn now(&self) -> OffsetDateTime {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixed_clock() {
+        let now = Date::from_calendar_date(2024, time::Month::December, 31)
+            .and_then(|date| date.with_hms(14, 0, 0))
+            .expect("valid date")
+            .assume_utc();
+        let clock = FixedClock(now);
+        assert_eq!(clock.now(), now);
+        assert_eq!(clock.today(UtcOffset::UTC).to_string(), "2024-12-31");
+        let brisbane = UtcOffset::from_hms(10, 0, 0).expect("valid offset");
+        assert_eq!(clock.today(brisbane).to_string(), "2025-01-01");
+    }
+}
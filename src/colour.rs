@@ -0,0 +1,128 @@
+//! Colours as sent by the API, e.g. "#5e5e5e"
+
+use std::{fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::BinError;
+
+#[derive(Debug, Clone, Eq)]
+/// A validated hex colour. Keeps the original text so it serializes back exactly as the API sent it.
+pub struct Rgb {
+    /// The red channel
+    pub red: u8,
+    /// The green channel
+    pub green: u8,
+    /// The blue channel
+    pub blue: u8,
+    raw: String,
+}
+
+impl Rgb {
+    /// Create a colour from its channels
+    pub fn new(red: u8, green: u8, blue: u8) -> Self {
+        Self {
+            red,
+            green,
+            blue,
+            raw: format!("#{red:02x}{green:02x}{blue:02x}"),
+        }
+    }
+
+    /// The colour as a lower case hex string, e.g. "#5e5e5e"
+    pub fn to_hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.red, self.green, self.blue)
+    }
+
+    /// The colour as the API sent it
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+}
+
+impl PartialEq for Rgb {
+    fn eq(&self, other: &Self) -> bool {
+        (self.red, self.green, self.blue) == (other.red, other.green, other.blue)
+    }
+}
+
+impl std::hash::Hash for Rgb {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        (self.red, self.green, self.blue).hash(state);
+    }
+}
+
+impl FromStr for Rgb {
+    type Err = BinError;
+
+    /// Parses "#rrggbb" or "#rgb", case insensitive
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || BinError::InvalidColour(s.to_string());
+        let hex = s.trim().strip_prefix('#').ok_or_else(invalid)?;
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+        let channel = |digits: &str| u8::from_str_radix(digits, 16).map_err(|_| invalid());
+        let (red, green, blue) = match hex.len() {
+            6 => (
+                channel(&hex[0..2])?,
+                channel(&hex[2..4])?,
+                channel(&hex[4..6])?,
+            ),
+            3 => (
+                channel(&hex[0..1].repeat(2))?,
+                channel(&hex[1..2].repeat(2))?,
+                channel(&hex[2..3].repeat(2))?,
+            ),
+            _ => return Err(invalid()),
+        };
+        Ok(Self {
+            red,
+            green,
+            blue,
+            raw: s.to_string(),
+        })
+    }
+}
+
+impl Display for Rgb {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.raw)
+    }
+}
+
+impl Serialize for Rgb {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.raw)
+    }
+}
+
+impl<'de> Deserialize<'de> for Rgb {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Rgb::from_str(&value).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rgb() {
+        let colour = Rgb::from_str("#5E5E5E").expect("Failed to parse colour");
+        assert_eq!(colour, Rgb::new(0x5e, 0x5e, 0x5e));
+        assert_eq!(colour.to_hex(), "#5e5e5e");
+        assert_eq!(colour.to_string(), "#5E5E5E");
+
+        let colour = Rgb::from_str("#fff").expect("Failed to parse colour");
+        assert_eq!(colour, Rgb::new(255, 255, 255));
+
+        for invalid in ["", "5e5e5e", "#5e5e5", "#gggggg", "#5e5e5e5e"] {
+            assert!(
+                Rgb::from_str(invalid).is_err(),
+                "{invalid} should be invalid"
+            );
+        }
+    }
+}
@@ -0,0 +1,159 @@
+//! Councils hosted on the waste-info.com.au platform
+
+use std::{fmt::Display, str::FromStr};
+
+use time::UtcOffset;
+
+use crate::{BinError, Locality, BASE_URL};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A council whose bin data is served by the waste-info.com.au platform
+pub struct Council {
+    /// The short key used to select the council, e.g. "brisbane"
+    pub key: String,
+    /// The display name, e.g. "Brisbane City Council"
+    pub name: String,
+    /// The base URL of the council's API, e.g. `https://brisbane.waste-info.com.au/api/v1/`
+    pub base_url: String,
+    /// The council's local timezone, used to work out what "today" is
+    pub timezone: UtcOffset,
+    /// Extra headers sent with every request
+    pub headers: Vec<(String, String)>,
+}
+
+impl Default for Council {
+    fn default() -> Self {
+        Self::brisbane()
+    }
+}
+
+impl Display for Council {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.name, self.key)
+    }
+}
+
+/// Queensland doesn't observe daylight saving, so every council we know about is on UTC+10
+const AEST: UtcOffset = match UtcOffset::from_hms(10, 0, 0) {
+    Ok(val) => val,
+    Err(_) => UtcOffset::UTC,
+};
+
+/// The lowercase words in a council name or key, ignoring punctuation
+fn words(name: &str) -> Vec<String> {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+impl Council {
+    fn new(key: &str, name: &str, base_url: &str) -> Self {
+        Self {
+            key: key.to_string(),
+            name: name.to_string(),
+            base_url: base_url.to_string(),
+            timezone: AEST,
+            headers: Vec::new(),
+        }
+    }
+
+    /// Brisbane City Council, the default
+    pub fn brisbane() -> Self {
+        Self::new("brisbane", "Brisbane City Council", BASE_URL)
+    }
+
+    /// Another council on the waste-info.com.au platform, e.g. `Council::custom("Example Shire Council",
+    /// "https://example.waste-info.com.au/api/v1/")`. The key is the name in lowercase, with dashes for spaces.
+    pub fn custom(name: &str, base_url: &str) -> Self {
+        let key = words(name).join("-");
+        Self::new(&key, name, base_url)
+    }
+
+    /// All the councils we know about. Others on the platform can be used with [Council::custom].
+    pub fn known() -> Vec<Self> {
+        vec![Self::brisbane()]
+    }
+
+    /// Builder method, adds a header sent with every request
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Builder method, sets the council's timezone
+    pub fn with_timezone(self, timezone: UtcOffset) -> Self {
+        Self { timezone, ..self }
+    }
+
+    /// Does this locality belong to this council? The [Locality::council] field has to be our name, or
+    /// contain our key as whole words, so "Brisbane City Council" matches "brisbane" but "Brisbaneville" doesn't.
+    pub fn matches_locality(&self, locality: &Locality) -> bool {
+        let council = words(&locality.council);
+        let key = words(&self.key);
+        council == words(&self.name)
+            || (!key.is_empty() && council.windows(key.len()).any(|window| window == key))
+    }
+}
+
+impl FromStr for Council {
+    type Err = BinError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let needle = s.trim().to_lowercase().replace([' ', '_'], "-");
+        Self::known()
+            .into_iter()
+            .find(|council| {
+                council.key == needle || council.name.to_lowercase() == s.to_lowercase()
+            })
+            .ok_or_else(|| BinError::UnknownCouncil(s.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_council_lookup() {
+        assert_eq!(Council::default(), Council::brisbane());
+        assert_eq!(
+            Council::from_str("Brisbane City Council").expect("Failed to find council"),
+            Council::brisbane()
+        );
+        assert_eq!(
+            Council::from_str(" BRISBANE ").expect("Failed to find council"),
+            Council::brisbane()
+        );
+        assert!(matches!(
+            Council::from_str("Gotham"),
+            Err(BinError::UnknownCouncil(_))
+        ));
+
+        let custom = Council::custom(
+            "Example Shire Council",
+            "https://example.waste-info.com.au/api/v1/",
+        );
+        assert_eq!(custom.key, "example-shire-council");
+        assert_eq!(custom.timezone, AEST);
+    }
+
+    #[test]
+    fn test_matches_locality() {
+        let locality = |council: &str| Locality {
+            id: 1,
+            name: "Springwood".to_string(),
+            postcode: None,
+            council: council.to_string(),
+        };
+        let logan = Council::custom("Logan City Council", "https://example.com/api/v1/");
+        assert!(logan.matches_locality(&locality("Logan City Council")));
+        assert!(logan.matches_locality(&locality("LOGAN CITY COUNCIL")));
+        assert!(!Council::brisbane().matches_locality(&locality("Logan City Council")));
+
+        assert!(Council::brisbane().matches_locality(&locality("Brisbane")));
+        assert!(Council::brisbane().matches_locality(&locality("Brisbane City Council")));
+        // only whole words count
+        assert!(!Council::brisbane().matches_locality(&locality("Brisbaneville Shire")));
+    }
+}
@@ -0,0 +1,256 @@
+//! CSV and TSV output, for loading bin days and listings into spreadsheets
+
+use std::io::Write;
+
+use serde::Serialize;
+
+use crate::{BinData, BinDay, Locality, Property, Street};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The field separator
+pub enum Delimiter {
+    /// Comma separated values
+    Comma,
+    /// Tab separated values
+    Tab,
+}
+
+impl Delimiter {
+    fn as_char(self) -> char {
+        match self {
+            Delimiter::Comma => ',',
+            Delimiter::Tab => '\t',
+        }
+    }
+}
+
+/// Quote a field if it contains the delimiter, a quote or a line break, doubling any quotes
+fn quote(field: &str, delimiter: Delimiter) -> String {
+    if field.contains([delimiter.as_char(), '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Write a header row followed by the rows, each terminated with CRLF as per RFC 4180
+pub fn write_table<I>(
+    writer: &mut impl Write,
+    delimiter: Delimiter,
+    header: &[&str],
+    rows: I,
+) -> std::io::Result<()>
+where
+    I: IntoIterator<Item = Vec<String>>,
+{
+    let separator = delimiter.as_char().to_string();
+    let header = header
+        .iter()
+        .map(|field| quote(field, delimiter))
+        .collect::<Vec<_>>();
+    write!(writer, "{}\r\n", header.join(&separator))?;
+    for row in rows {
+        let row = row
+            .iter()
+            .map(|field| quote(field, delimiter))
+            .collect::<Vec<_>>();
+        write!(writer, "{}\r\n", row.join(&separator))?;
+    }
+    Ok(())
+}
+
+/// Write one row per bin day, with the property details repeated on each row
+pub fn write_bin_days(
+    writer: &mut impl Write,
+    delimiter: Delimiter,
+    bin_data: &BinData,
+    bin_days: &[&BinDay],
+) -> std::io::Result<()> {
+    let property_id = bin_data
+        .property
+        .id
+        .map(|id| id.to_string())
+        .unwrap_or_default();
+    write_table(
+        writer,
+        delimiter,
+        &[
+            "date",
+            "weekday",
+            "name",
+            "event_type",
+            "colour",
+            "property_id",
+            "address",
+            "zone",
+        ],
+        bin_days.iter().map(|day| {
+            vec![
+                day.start.to_string(),
+                day.start.weekday().to_string(),
+                day.name.clone().unwrap_or_default(),
+                day.event_type.to_string(),
+                day.color.to_string(),
+                property_id.clone(),
+                bin_data.property.address.clone(),
+                bin_data.property.zone.clone(),
+            ]
+        }),
+    )
+}
+
+/// A row in one of the locality, street or property listings
+pub trait Listing: Serialize {
+    /// The column names
+    const HEADER: &'static [&'static str];
+    /// The name to filter on, e.g. the street name
+    fn name(&self) -> &str;
+    /// The fields, in the same order as [Listing::HEADER]
+    fn row(&self) -> Vec<String>;
+
+    /// Does the name contain `filter`, ignoring case?
+    fn matches(&self, filter: &str) -> bool {
+        self.name().to_lowercase().contains(&filter.to_lowercase())
+    }
+}
+
+impl Listing for Locality {
+    const HEADER: &'static [&'static str] = &["id", "name", "postcode", "council"];
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.name.clone(),
+            self.postcode.clone().unwrap_or_default(),
+            self.council.clone(),
+        ]
+    }
+}
+
+impl Listing for Street {
+    const HEADER: &'static [&'static str] = &["id", "name", "locality"];
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.name.clone(),
+            self.locality.clone(),
+        ]
+    }
+}
+
+impl Listing for Property {
+    const HEADER: &'static [&'static str] = &["id", "name", "zone"];
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![self.id.to_string(), self.name.clone(), self.zone.clone()]
+    }
+}
+
+/// Write one row per item in a listing
+pub fn write_listing<T: Listing>(
+    writer: &mut impl Write,
+    delimiter: Delimiter,
+    items: &[T],
+) -> std::io::Result<()> {
+    write_table(writer, delimiter, T::HEADER, items.iter().map(Listing::row))
+}
+
+/// Write one row per locality
+pub fn write_localities(
+    writer: &mut impl Write,
+    delimiter: Delimiter,
+    localities: &[Locality],
+) -> std::io::Result<()> {
+    write_listing(writer, delimiter, localities)
+}
+
+/// Write one row per street
+pub fn write_streets(
+    writer: &mut impl Write,
+    delimiter: Delimiter,
+    streets: &[Street],
+) -> std::io::Result<()> {
+    write_listing(writer, delimiter, streets)
+}
+
+/// Write one row per property
+pub fn write_properties(
+    writer: &mut impl Write,
+    delimiter: Delimiter,
+    properties: &[Property],
+) -> std::io::Result<()> {
+    write_listing(writer, delimiter, properties)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use serde_json::json;
+
+    #[test]
+    fn test_quoting() {
+        assert_eq!(quote("plain", Delimiter::Comma), "plain");
+        assert_eq!(
+            quote("2 Boundary St, West End", Delimiter::Comma),
+            "\"2 Boundary St, West End\""
+        );
+        assert_eq!(
+            quote("2 Boundary St, West End", Delimiter::Tab),
+            "2 Boundary St, West End"
+        );
+        assert_eq!(quote("say \"hi\"", Delimiter::Tab), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn test_listing_filter() {
+        let street: Street = serde_json::from_value(json!({
+            "id": 1, "name": "Boundary St", "locality": "West End"
+        }))
+        .expect("Failed to parse street");
+        assert!(street.matches("bound"));
+        assert!(street.matches("ST"));
+        assert!(!street.matches("vulture"));
+
+        let mut buf = Vec::new();
+        write_streets(&mut buf, Delimiter::Tab, &[street]).expect("Failed to write TSV");
+        assert_eq!(
+            String::from_utf8(buf).expect("valid utf8"),
+            "id\tname\tlocality\r\n1\tBoundary St\tWest End\r\n"
+        );
+    }
+
+    #[test]
+    fn test_bin_days_csv() {
+        let bin_data = fixtures::property_bin_data();
+        let bin_day = fixtures::recycling("2025-10-14");
+
+        let mut buf = Vec::new();
+        write_bin_days(&mut buf, Delimiter::Comma, &bin_data, &[&bin_day])
+            .expect("Failed to write CSV");
+        assert_eq!(
+            String::from_utf8(buf).expect("valid utf8"),
+            "date,weekday,name,event_type,colour,property_id,address,zone\r\n\
+             2025-10-14,Tuesday,Recycling,recycle,#ffd700,2695626,\"2 Boundary St, West End\",Zone 1\r\n"
+        );
+
+        let mut buf = Vec::new();
+        write_bin_days(&mut buf, Delimiter::Tab, &bin_data, &[&bin_day])
+            .expect("Failed to write TSV");
+        let tsv = String::from_utf8(buf).expect("valid utf8");
+        assert!(tsv.contains("\t2 Boundary St, West End\t"));
+    }
+}
@@ -0,0 +1,266 @@
+//! Error types returned by the library
+
+use std::fmt::Display;
+
+use crate::{retry::Attempt, ErrorResponse, Property};
+
+#[derive(Debug)]
+/// Errors returned when querying the bin data API
+pub enum BinError {
+    /// Failed to build a request URL
+    InvalidUrl {
+        /// The URL (or fragment) that failed to parse
+        url: String,
+        /// The underlying parse error
+        source: <reqwest::Url as std::str::FromStr>::Err,
+    },
+    /// The request couldn't be sent, or the response body couldn't be read
+    Transport {
+        /// What we were trying to do, e.g. "query localities"
+        context: String,
+        /// The underlying transport error
+        source: reqwest::Error,
+    },
+    /// The API responded with an unexpected HTTP status
+    HttpStatus {
+        /// The URL that was requested
+        url: String,
+        /// The HTTP status code
+        status: u16,
+    },
+    /// The API responded with an [ErrorResponse]
+    Api(ErrorResponse),
+    /// The response JSON didn't match the expected shape
+    Json {
+        /// What we were trying to parse, e.g. "localities"
+        context: String,
+        /// The underlying deserialization error
+        source: serde_json::Error,
+    },
+    /// The response was valid JSON, but not laid out how we expected
+    InvalidResponse(String),
+    /// The address couldn't be parsed
+    InvalidAddress(String),
+    /// A colour wasn't a valid hex colour, e.g. "#5e5e5e"
+    InvalidColour(String),
+    /// The council isn't one we know about
+    UnknownCouncil(String),
+    /// No locality matched the search term
+    LocalityNotFound {
+        /// The suburb searched for
+        name: String,
+        /// The closest locality names, best first
+        suggestions: Vec<String>,
+    },
+    /// No street matched the search term
+    StreetNotFound {
+        /// The street searched for
+        name: String,
+        /// The closest street names in the locality, best first
+        suggestions: Vec<String>,
+    },
+    /// No property matched the search term (an address or property ID)
+    PropertyNotFound(String),
+    /// The suburb was found, but not in the postcode given with it
+    PostcodeMismatch {
+        /// The suburb searched for
+        suburb: String,
+        /// The postcode given
+        postcode: String,
+        /// The postcodes the suburb is actually in
+        expected: Vec<String>,
+    },
+    /// More than one property matched the address, e.g. the units in a block
+    AmbiguousAddress {
+        /// The address searched for
+        address: String,
+        /// The matching properties
+        candidates: Vec<Property>,
+    },
+    /// The requested range of dates is empty or out of bounds
+    InvalidDateRange {
+        /// The first date requested
+        start: time::Date,
+        /// The last date requested
+        end: time::Date,
+    },
+    /// A request kept failing, and we gave up retrying it
+    RetriesExhausted {
+        /// Every attempt, in order
+        attempts: Vec<Attempt>,
+        /// The error from the last attempt
+        last: Box<BinError>,
+    },
+    /// Working offline, and what was asked for isn't in the cache
+    NotCached(String),
+    /// Failed to parse, format or calculate a date
+    Date {
+        /// What we were trying to do, e.g. "parse bin day start date"
+        context: String,
+        /// The underlying date error
+        source: time::error::Error,
+    },
+}
+
+/// Finish a not-found message with the closest matches, if there are any
+fn write_suggestions(f: &mut std::fmt::Formatter<'_>, suggestions: &[String]) -> std::fmt::Result {
+    if suggestions.is_empty() {
+        Ok(())
+    } else {
+        write!(f, ", did you mean {}?", suggestions.join(", "))
+    }
+}
+
+impl Display for BinError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BinError::InvalidUrl { url, .. } => write!(f, "Failed to build URL from {url}"),
+            BinError::Transport { context, .. } => write!(f, "Failed to {context}"),
+            BinError::HttpStatus { url, status } => {
+                write!(f, "API returned HTTP status {status} for {url}")
+            }
+            BinError::Api(err) => write!(
+                f,
+                "API returned an error: {} (status {})",
+                err.error, err.status
+            ),
+            BinError::Json { context, .. } => write!(f, "Failed to parse {context} JSON"),
+            BinError::InvalidResponse(msg) => write!(f, "Unexpected API response: {msg}"),
+            BinError::InvalidAddress(msg) => write!(f, "{msg}"),
+            BinError::InvalidColour(value) => write!(f, "Invalid colour {value:?}"),
+            BinError::UnknownCouncil(name) => write!(f, "Unknown council {name:?}"),
+            BinError::LocalityNotFound { name, suggestions } => {
+                write!(f, "Suburb {name} not found")?;
+                write_suggestions(f, suggestions)
+            }
+            BinError::StreetNotFound { name, suggestions } => {
+                write!(f, "Street '{name}' not found")?;
+                write_suggestions(f, suggestions)
+            }
+            BinError::PropertyNotFound(name) => write!(f, "Property {name} not found"),
+            BinError::PostcodeMismatch {
+                suburb,
+                postcode,
+                expected,
+            } => write!(
+                f,
+                "Suburb {suburb} isn't in postcode {postcode}, it's in {}",
+                expected.join(" or ")
+            ),
+            BinError::AmbiguousAddress {
+                address,
+                candidates,
+            } => write!(
+                f,
+                "Address {address} matches more than one property: {}",
+                candidates
+                    .iter()
+                    .map(|p| p.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            BinError::InvalidDateRange { start, end } => {
+                write!(f, "Invalid date range {start} to {end}")
+            }
+            BinError::RetriesExhausted { attempts, last } => {
+                let attempts = attempts
+                    .iter()
+                    .map(Attempt::to_string)
+                    .collect::<Vec<_>>()
+                    .join("; ");
+                write!(f, "{last} (gave up after: {attempts})")
+            }
+            BinError::NotCached(what) => write!(f, "No cached {what} to use offline"),
+            BinError::Date { context, .. } => write!(f, "Failed to {context}"),
+        }
+    }
+}
+
+impl std::error::Error for BinError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BinError::InvalidUrl { source, .. } => Some(source),
+            BinError::Transport { source, .. } => Some(source),
+            BinError::Json { source, .. } => Some(source),
+            BinError::Date { source, .. } => Some(source),
+            BinError::RetriesExhausted { last, .. } => Some(last.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl BinError {
+    /// Returns true if this error means the thing being searched for doesn't exist
+    pub fn is_not_found(&self) -> bool {
+        matches!(
+            self,
+            BinError::LocalityNotFound { .. }
+                | BinError::StreetNotFound { .. }
+                | BinError::PropertyNotFound(_)
+        )
+    }
+
+    /// Returns true if this error means the server couldn't be reached or is having problems,
+    /// so trying again later (or using cached data) might work
+    pub fn is_unavailable(&self) -> bool {
+        match self {
+            BinError::Transport { .. } => true,
+            BinError::HttpStatus { status, .. } => *status >= 500,
+            BinError::Api(err) => err.status >= 500,
+            BinError::RetriesExhausted { last, .. } => last.is_unavailable(),
+            _ => false,
+        }
+    }
+
+    pub(crate) fn date(context: &str, source: impl Into<time::error::Error>) -> Self {
+        BinError::Date {
+            context: context.to_string(),
+            source: source.into(),
+        }
+    }
+
+    pub(crate) fn transport(context: &str, source: reqwest::Error) -> Self {
+        BinError::Transport {
+            context: context.to_string(),
+            source,
+        }
+    }
+
+    pub(crate) fn json(context: &str, source: serde_json::Error) -> Self {
+        BinError::Json {
+            context: context.to_string(),
+            source,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn test_source_chaining() {
+        let json_err = serde_json::from_str::<u32>("nope").expect_err("should fail");
+        let err = BinError::json("localities", json_err);
+        assert!(err.source().is_some());
+        assert_eq!(err.to_string(), "Failed to parse localities JSON");
+
+        let err = BinError::LocalityNotFound {
+            name: "Nowhere".to_string(),
+            suggestions: Vec::new(),
+        };
+        assert!(err.source().is_none());
+        assert!(err.is_not_found());
+        assert_eq!(err.to_string(), "Suburb Nowhere not found");
+
+        let err = BinError::StreetNotFound {
+            name: "Boundry St".to_string(),
+            suggestions: vec!["Boundary St".to_string(), "Bunda St".to_string()],
+        };
+        assert_eq!(
+            err.to_string(),
+            "Street 'Boundry St' not found, did you mean Boundary St, Bunda St?"
+        );
+    }
+}
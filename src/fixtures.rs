@@ -0,0 +1,56 @@
+//! Bin data shared by the tests, as the API sends it for 2 Boundary St, West End
+
+use serde_json::{json, Value};
+
+use crate::{BinData, BinDay};
+
+/// The property's bin data, general waste on Tuesdays
+pub(crate) fn bin_data_json() -> Value {
+    json!({
+        "property": {
+            "collection_day": 2,
+            "zone": "Zone 1",
+            "clean_up_code": "C1",
+            "address": "2 Boundary St West End",
+            "service_type": "Garbage",
+            "collections": [],
+        },
+        "color": "#5e5e5e",
+        "textColor": "#ffffff",
+        "borderColor": "#5e5e5e",
+        "dow": [2],
+        "daysOfWeek": [2],
+        "start_date": "2024-01-02",
+        "event_type": "waste",
+    })
+}
+
+/// A recycling bin day on `start`, e.g. "2025-10-14"
+pub(crate) fn recycling_json(start: &str) -> Value {
+    json!({
+        "name": "Recycling",
+        "color": "#ffd700",
+        "textColor": "#000000",
+        "borderColor": "#ffd700",
+        "start": start,
+        "event_type": "recycle",
+    })
+}
+
+/// [bin_data_json], parsed
+pub(crate) fn bin_data() -> BinData {
+    serde_json::from_value(bin_data_json()).expect("Failed to parse bin data")
+}
+
+/// [bin_data], with the property ID and address filled in the way [crate::BinClient::get_property] does
+pub(crate) fn property_bin_data() -> BinData {
+    let mut bin_data = bin_data();
+    bin_data.property.id = Some(2695626);
+    bin_data.property.address = "2 Boundary St, West End".to_string();
+    bin_data
+}
+
+/// [recycling_json], parsed
+pub(crate) fn recycling(start: &str) -> BinDay {
+    serde_json::from_value(recycling_json(start)).expect("Failed to parse bin day")
+}
@@ -0,0 +1,304 @@
+//! iCalendar (RFC 5545) export, so bin days can be subscribed to from a calendar app
+
+use std::io::Write;
+
+use time::{Date, Duration, OffsetDateTime, Time, UtcOffset};
+
+use crate::{
+    schedule::{CollectionSource, Schedule},
+    BinData, BinDay, BinKind, Rgb,
+};
+
+/// The CSS3 colour names we pick from for the RFC 7986 COLOR property, which doesn't allow hex values
+const CSS_COLOURS: &[(&str, (u8, u8, u8))] = &[
+    ("black", (0, 0, 0)),
+    ("dimgray", (105, 105, 105)),
+    ("gray", (128, 128, 128)),
+    ("silver", (192, 192, 192)),
+    ("white", (255, 255, 255)),
+    ("red", (255, 0, 0)),
+    ("darkred", (139, 0, 0)),
+    ("maroon", (128, 0, 0)),
+    ("orange", (255, 165, 0)),
+    ("gold", (255, 215, 0)),
+    ("yellow", (255, 255, 0)),
+    ("yellowgreen", (154, 205, 50)),
+    ("limegreen", (50, 205, 50)),
+    ("green", (0, 128, 0)),
+    ("darkgreen", (0, 100, 0)),
+    ("olive", (128, 128, 0)),
+    ("teal", (0, 128, 128)),
+    ("blue", (0, 0, 255)),
+    ("navy", (0, 0, 128)),
+    ("purple", (128, 0, 128)),
+    ("brown", (165, 42, 42)),
+];
+
+#[derive(Debug, Clone)]
+/// Options for [write_ics]
+pub struct IcsOptions {
+    /// When the calendar was generated, used for DTSTAMP
+    pub stamp: OffsetDateTime,
+    /// Add a reminder at this time on the evening before each collection, e.g. 18:00
+    pub alarm: Option<Time>,
+    /// The dates to add the regular weekly pickup for, defaults to the span of the bin days
+    pub range: Option<(Date, Date)>,
+}
+
+impl IcsOptions {
+    /// Options with no alarm, stamped with `stamp`
+    pub fn new(stamp: OffsetDateTime) -> Self {
+        Self {
+            stamp,
+            alarm: None,
+            range: None,
+        }
+    }
+}
+
+/// The nearest CSS3 colour name to an API colour
+fn css_colour_name(colour: &Rgb) -> &'static str {
+    let distance = |(red, green, blue): (u8, u8, u8)| {
+        let diff = |a: u8, b: u8| (i32::from(a) - i32::from(b)).pow(2);
+        diff(red, colour.red) + diff(green, colour.green) + diff(blue, colour.blue)
+    };
+    CSS_COLOURS
+        .iter()
+        .min_by_key(|(_, rgb)| distance(*rgb))
+        .map_or("gray", |(name, _)| name)
+}
+
+/// A short machine-friendly name for a kind, used in UIDs
+fn kind_slug(kind: &BinKind) -> String {
+    match kind {
+        BinKind::General => "general".to_string(),
+        BinKind::Recycling => "recycling".to_string(),
+        BinKind::GreenWaste => "green-waste".to_string(),
+        BinKind::KerbsideCleanup => "kerbside-cleanup".to_string(),
+        BinKind::Unknown { event_type, .. } => format!(
+            "other-{}",
+            event_type
+                .as_str()
+                .chars()
+                .filter(|c| c.is_ascii_alphanumeric())
+                .collect::<String>()
+        ),
+    }
+}
+
+/// Escape TEXT values, see RFC 5545 section 3.3.11
+fn escape_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Write a content line, folded at 75 octets and terminated with CRLF
+fn write_line(writer: &mut impl Write, line: &str) -> std::io::Result<()> {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            writer.write_all(b"\r\n ")?;
+            width = 1;
+        }
+        let mut buf = [0; 4];
+        writer.write_all(c.encode_utf8(&mut buf).as_bytes())?;
+        width += c.len_utf8();
+    }
+    writer.write_all(b"\r\n")
+}
+
+/// Format a date as YYYYMMDD
+fn ics_date(date: Date) -> String {
+    format!(
+        "{:04}{:02}{:02}",
+        date.year(),
+        u8::from(date.month()),
+        date.day()
+    )
+}
+
+struct Event {
+    date: Date,
+    kind: BinKind,
+    summary: String,
+    colour: Rgb,
+    source: CollectionSource,
+}
+
+/// Write the bin days for a property as an iCalendar, with one all-day VEVENT per bin per collection.
+///
+/// The events come from the [Schedule] projected over `options.range`, so the regular weekly pickup is
+/// included alongside the bin days. Predicted collections are marked `STATUS:TENTATIVE`.
+pub fn write_ics(
+    writer: &mut impl Write,
+    bin_data: &BinData,
+    bin_days: &[BinDay],
+    options: &IcsOptions,
+) -> std::io::Result<()> {
+    let property_id = bin_data
+        .property
+        .id
+        .map_or_else(|| "unknown".to_string(), |id| id.to_string());
+
+    let range = options.range.or_else(|| {
+        let first = bin_days.iter().map(|day| day.start).min()?;
+        let last = bin_days.iter().map(|day| day.start).max()?;
+        Some((first, last))
+    });
+    let mut events: Vec<Event> = Vec::new();
+    if let Some((start, end)) = range {
+        let weeks = u32::try_from(((end - start).whole_days() + 7) / 7).unwrap_or_default();
+        let projected = Schedule::infer(bin_data, bin_days, start).project(bin_days, start, weeks);
+        for item in projected.into_iter().filter(|item| item.date <= end) {
+            let summary = match bin_days
+                .iter()
+                .find(|day| day.start == item.date && day.kind() == item.kind)
+            {
+                Some(day) => day.name.clone().unwrap_or_else(|| item.kind.to_string()),
+                None => format!("{} bin (predicted)", item.kind),
+            };
+            let colour = item.colour(bin_data, bin_days).clone();
+            events.push(Event {
+                date: item.date,
+                kind: item.kind,
+                summary,
+                colour,
+                source: item.source,
+            });
+        }
+    }
+
+    let stamp = options.stamp.to_offset(UtcOffset::UTC);
+    let stamp = format!(
+        "{}T{:02}{:02}{:02}Z",
+        ics_date(stamp.date()),
+        stamp.hour(),
+        stamp.minute(),
+        stamp.second()
+    );
+
+    write_line(writer, "BEGIN:VCALENDAR")?;
+    write_line(writer, "VERSION:2.0")?;
+    write_line(
+        writer,
+        &format!(
+            "PRODID:-//brisbane-bin-data//{}//EN",
+            env!("CARGO_PKG_VERSION")
+        ),
+    )?;
+    write_line(writer, "CALSCALE:GREGORIAN")?;
+    write_line(
+        writer,
+        &format!(
+            "X-WR-CALNAME:{}",
+            escape_text(&format!("Bin days for {}", bin_data.property.address))
+        ),
+    )?;
+
+    for event in events {
+        write_line(writer, "BEGIN:VEVENT")?;
+        write_line(
+            writer,
+            &format!(
+                "UID:{property_id}-{}-{}@brisbane-bin-data",
+                ics_date(event.date),
+                kind_slug(&event.kind)
+            ),
+        )?;
+        write_line(writer, &format!("DTSTAMP:{stamp}"))?;
+        write_line(
+            writer,
+            &format!("DTSTART;VALUE=DATE:{}", ics_date(event.date)),
+        )?;
+        let end = event.date.next_day().unwrap_or(event.date);
+        write_line(writer, &format!("DTEND;VALUE=DATE:{}", ics_date(end)))?;
+        write_line(writer, &format!("SUMMARY:{}", escape_text(&event.summary)))?;
+        write_line(writer, &format!("COLOR:{}", css_colour_name(&event.colour)))?;
+        write_line(writer, "TRANSP:TRANSPARENT")?;
+        write_line(
+            writer,
+            match event.source {
+                CollectionSource::Confirmed => "STATUS:CONFIRMED",
+                CollectionSource::Predicted => "STATUS:TENTATIVE",
+            },
+        )?;
+        if let Some(alarm) = options.alarm {
+            // the trigger is relative to midnight at the start of the collection day
+            let before = Duration::DAY - (alarm - Time::MIDNIGHT);
+            write_line(writer, "BEGIN:VALARM")?;
+            write_line(writer, "ACTION:DISPLAY")?;
+            write_line(
+                writer,
+                &format!(
+                    "DESCRIPTION:{}",
+                    escape_text(&format!("Put out the {}", event.kind))
+                ),
+            )?;
+            write_line(writer, &format!("TRIGGER:-PT{}M", before.whole_minutes()))?;
+            write_line(writer, "END:VALARM")?;
+        }
+        write_line(writer, "END:VEVENT")?;
+    }
+    write_line(writer, "END:VCALENDAR")
+}
+
+/// Render the bin days for a property as an iCalendar string, see [write_ics]
+pub fn to_ics(bin_data: &BinData, bin_days: &[BinDay], options: &IcsOptions) -> String {
+    let mut buf = Vec::new();
+    // writing to a Vec can't fail
+    let _ = write_ics(&mut buf, bin_data, bin_days, options);
+    String::from_utf8_lossy(&buf).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn test_ics() {
+        let bin_data = fixtures::property_bin_data();
+        let bin_days = vec![fixtures::recycling("2025-10-14")];
+        let mut options = IcsOptions::new(OffsetDateTime::UNIX_EPOCH);
+        options.alarm = Some(Time::from_hms(18, 0, 0).expect("valid time"));
+        let start = Date::from_calendar_date(2025, time::Month::October, 1).expect("valid date");
+        let end = Date::from_calendar_date(2025, time::Month::October, 31).expect("valid date");
+        options.range = Some((start, end));
+
+        let ics = to_ics(&bin_data, &bin_days, &options);
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert!(ics.contains("UID:2695626-20251014-recycling@brisbane-bin-data\r\n"));
+        assert!(ics.contains("UID:2695626-20251014-general@brisbane-bin-data\r\n"));
+        assert!(ics.contains("DTSTART;VALUE=DATE:20251014\r\nDTEND;VALUE=DATE:20251015\r\n"));
+        assert!(ics.contains("COLOR:gold\r\n"));
+        assert!(ics.contains("X-WR-CALNAME:Bin days for 2 Boundary St\\, West End\r\n"));
+        assert!(ics.contains("TRIGGER:-PT360M\r\n"));
+        assert!(ics.contains("DTSTAMP:19700101T000000Z\r\n"));
+        assert!(ics.contains("SUMMARY:Recycling\r\n"));
+        assert!(ics.contains("SUMMARY:general bin (predicted)\r\n"));
+        // the recycling fortnight after the listed one is predicted
+        assert!(ics.contains(
+            "UID:2695626-20251028-recycling@brisbane-bin-data\r\nDTSTAMP:19700101T000000Z\r\n\
+             DTSTART;VALUE=DATE:20251028\r\nDTEND;VALUE=DATE:20251029\r\n\
+             SUMMARY:recycling bin (predicted)\r\nCOLOR:gold\r\nTRANSP:TRANSPARENT\r\n\
+             STATUS:TENTATIVE\r\n"
+        ));
+        assert_eq!(ics.matches("STATUS:CONFIRMED").count(), 1);
+        // four Tuesdays of general waste, plus two recycling
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 6);
+    }
+
+    #[test]
+    fn test_line_folding() {
+        let mut buf = Vec::new();
+        write_line(&mut buf, &"x".repeat(100)).expect("Failed to write");
+        let folded = String::from_utf8(buf).expect("valid utf8");
+        let lines = folded.split("\r\n").collect::<Vec<_>>();
+        assert_eq!(lines[0].len(), 75);
+        assert_eq!(lines[1], format!(" {}", "x".repeat(25)));
+    }
+}
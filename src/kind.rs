@@ -0,0 +1,215 @@
+//! Working out which bin a [BinDay] is about
+
+use std::fmt::Display;
+
+use serde::Serialize;
+
+use crate::{BinData, BinDay, EventType, Rgb};
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+/// The waste stream a bin day is for
+pub enum BinKind {
+    /// General waste, usually a red or dark lid
+    General,
+    /// Recycling, usually a yellow lid
+    Recycling,
+    /// Green waste, usually a lime green lid
+    GreenWaste,
+    /// A kerbside clean-up, not a bin at all
+    KerbsideCleanup,
+    /// Couldn't work it out, so here's what we were given
+    Unknown {
+        /// The event type
+        event_type: EventType,
+        /// The bin day name, if there was one
+        name: Option<String>,
+        /// The bin day colour
+        color: Rgb,
+    },
+}
+
+impl Display for BinKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BinKind::General => write!(f, "general"),
+            BinKind::Recycling => write!(f, "recycling"),
+            BinKind::GreenWaste => write!(f, "green waste"),
+            BinKind::KerbsideCleanup => write!(f, "kerbside clean-up"),
+            BinKind::Unknown {
+                name: Some(name), ..
+            } => write!(f, "{name}"),
+            BinKind::Unknown { event_type, .. } => write!(f, "{event_type}"),
+        }
+    }
+}
+
+impl BinKind {
+    /// Is this a bin that goes out on the kerb? Clean-ups and unknown events aren't.
+    pub fn is_bin(&self) -> bool {
+        matches!(
+            self,
+            BinKind::General | BinKind::Recycling | BinKind::GreenWaste
+        )
+    }
+
+    /// Guess the kind from the words in a name or description, e.g. "Green Bin"
+    fn from_text(text: &str) -> Option<Self> {
+        let text = text.to_lowercase();
+        // check the more specific streams first, "green waste" contains "waste"
+        if text.contains("recycl") {
+            Some(BinKind::Recycling)
+        } else if ["green", "garden", "organic", "fogo"]
+            .iter()
+            .any(|word| text.contains(word))
+        {
+            Some(BinKind::GreenWaste)
+        } else if ["clean up", "clean-up", "cleanup", "kerbside"]
+            .iter()
+            .any(|word| text.contains(word))
+        {
+            Some(BinKind::KerbsideCleanup)
+        } else if ["general", "waste", "rubbish", "garbage", "landfill"]
+            .iter()
+            .any(|word| text.contains(word))
+        {
+            Some(BinKind::General)
+        } else {
+            None
+        }
+    }
+
+    /// Guess the kind from a lid colour, going by the Australian standard (AS 4123.7) palette
+    fn from_colour(colour: &Rgb) -> Option<Self> {
+        let (red, green, blue) = (
+            i32::from(colour.red),
+            i32::from(colour.green),
+            i32::from(colour.blue),
+        );
+        let max = red.max(green).max(blue);
+        let min = red.min(green).min(blue);
+        let chroma = max - min;
+        // greys and blacks are general waste lids
+        if chroma < 40 {
+            return Some(BinKind::General);
+        }
+        let hue = if max == red {
+            60 * (green - blue) / chroma
+        } else if max == green {
+            60 * (blue - red) / chroma + 120
+        } else {
+            60 * (red - green) / chroma + 240
+        }
+        .rem_euclid(360);
+        match hue {
+            0..=20 | 330..=359 => Some(BinKind::General),
+            40..=70 => Some(BinKind::Recycling),
+            71..=170 => Some(BinKind::GreenWaste),
+            _ => None,
+        }
+    }
+
+    /// Work out the kind, going by the event type, then the name and description, then the colour
+    fn classify(
+        event_type: &EventType,
+        name: Option<&str>,
+        description: Option<&str>,
+        color: &Rgb,
+    ) -> Self {
+        let from_event = match event_type {
+            EventType::Collection => Some(BinKind::General),
+            EventType::Recycling => Some(BinKind::Recycling),
+            EventType::GreenWaste => Some(BinKind::GreenWaste),
+            EventType::KerbsideCleanup => Some(BinKind::KerbsideCleanup),
+            EventType::Other(_) => None,
+        };
+        from_event
+            .or_else(|| name.and_then(BinKind::from_text))
+            .or_else(|| description.and_then(BinKind::from_text))
+            .or_else(|| BinKind::from_colour(color))
+            .unwrap_or_else(|| BinKind::Unknown {
+                event_type: event_type.clone(),
+                name: name.map(|val| val.to_string()),
+                color: color.clone(),
+            })
+    }
+}
+
+impl BinDay {
+    /// Work out which bin this day is for, going by the event type, then the name and description, then the colour
+    pub fn kind(&self) -> BinKind {
+        BinKind::classify(
+            &self.event_type,
+            self.name.as_deref(),
+            self.description.as_deref(),
+            &self.color,
+        )
+    }
+}
+
+impl BinData {
+    /// Work out which bin the property's regular weekly collection is for, usually [BinKind::General]
+    pub fn kind(&self) -> BinKind {
+        BinKind::classify(&self.event_type, None, None, &self.color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn bin_day(event_type: &str, name: Option<&str>, color: &str) -> BinDay {
+        BinDay {
+            id: None,
+            name: name.map(|val| val.to_string()),
+            description: None,
+            color: Rgb::from_str(color).expect("valid colour"),
+            text_colour: Rgb::new(0, 0, 0),
+            border_colour: Rgb::new(0, 0, 0),
+            start: time::Date::MIN,
+            event_type: EventType::from(event_type.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_kind_from_event_type() {
+        assert_eq!(
+            bin_day("recycle", None, "#000000").kind(),
+            BinKind::Recycling
+        );
+        assert_eq!(
+            bin_day("organic", None, "#000000").kind(),
+            BinKind::GreenWaste
+        );
+        assert_eq!(bin_day("waste", None, "#ffd700").kind(), BinKind::General);
+    }
+
+    #[test]
+    fn test_kind_from_name() {
+        assert_eq!(
+            bin_day("event", Some("Green Waste Bin"), "#000000").kind(),
+            BinKind::GreenWaste
+        );
+        assert_eq!(
+            bin_day("event", Some("Kerbside Clean Up"), "#000000").kind(),
+            BinKind::KerbsideCleanup
+        );
+    }
+
+    #[test]
+    fn test_kind_from_colour() {
+        assert_eq!(bin_day("event", None, "#ffd700").kind(), BinKind::Recycling);
+        assert_eq!(
+            bin_day("event", None, "#7cfc00").kind(),
+            BinKind::GreenWaste
+        );
+        assert_eq!(bin_day("event", None, "#d32f2f").kind(), BinKind::General);
+        assert_eq!(bin_day("event", None, "#5e5e5e").kind(), BinKind::General);
+
+        let kind = bin_day("event", Some("Mystery"), "#1e90ff").kind();
+        assert!(matches!(kind, BinKind::Unknown { .. }));
+        assert_eq!(kind.to_string(), "Mystery");
+        assert!(!kind.is_bin());
+    }
+}
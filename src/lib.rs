@@ -16,28 +16,93 @@
 #![deny(clippy::unreachable)]
 #![deny(clippy::unwrap_used)]
 
+pub mod address;
+pub mod cache;
+pub mod calendar;
 pub mod cli;
-
-use std::{fmt::Display, str::FromStr};
-
-use reqwest::Url;
-use serde::{Deserialize, Serialize};
+pub mod clock;
+pub mod colour;
+pub mod council;
+pub mod delimited;
+pub mod error;
+#[cfg(test)]
+mod fixtures;
+pub mod ics;
+pub mod kind;
+pub mod matching;
+pub mod prompt;
+pub mod rate_limit;
+pub mod render;
+pub mod retry;
+pub mod schedule;
+pub mod terminal;
+
+use std::{fmt::Display, str::FromStr, sync::Arc};
+
+use address::{HouseNumber, LocalityParts, NumberMatch, Premises, PropertyName};
+use cache::{Cache, CacheEntry, CachedSchedule, OfflineMode};
+pub use clock::{Clock, FixedClock, SystemClock};
+pub use colour::Rgb;
+pub use council::Council;
+pub use error::BinError;
+pub use kind::BinKind;
+use rate_limit::{RateLimit, RateLimiter, DEFAULT_USER_AGENT};
+pub use render::{OutputFormat, RenderOptions, Renderer, ScheduleView};
+use reqwest::{
+    header::{
+        HeaderMap, HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, USER_AGENT,
+    },
+    StatusCode, Url,
+};
+use retry::{Attempt, RetryPolicy};
+pub use schedule::{NextCollection, Schedule, ScheduledCollection};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::Value;
-use time::{format_description, util::days_in_month, Date};
+use time::{format_description, util::days_in_month, Date, Duration, OffsetDateTime, UtcOffset};
 
 /// The base URL for the Brisbane City Council bin data API
 pub const BASE_URL: &str = "https://brisbane.waste-info.com.au/api/v1/";
 
-/// Get the full URL for a given endpoint
+/// Get the full URL for a given endpoint on the default [BASE_URL], see [BinClient::endpoint_url] for a configured client
 pub fn get_url(filename: &str) -> String {
     format!("{BASE_URL}{filename}")
 }
 
+/// The longest range of dates (in days) we ask the API for in a single request
+pub const MAX_QUERY_DAYS: i64 = 62;
+
+/// The default range of dates to query, from the first to the last day of the month containing `today`
+pub fn month_window(today: Date) -> Result<(Date, Date), BinError> {
+    let start = today
+        .replace_day(1)
+        .map_err(|err| BinError::date("calculate start date", err))?;
+    let end = today
+        .replace_day(days_in_month(today.month(), today.year()))
+        .map_err(|err| BinError::date("calculate end date", err))?;
+    Ok((start, end))
+}
+
+/// The UTC timestamp of local midnight at the start of `date`, e.g. 14:00 the day before for Brisbane
+fn window_boundary(date: Date, timezone: UtcOffset) -> OffsetDateTime {
+    date.midnight()
+        .assume_offset(timezone)
+        .to_offset(UtcOffset::UTC)
+}
+
+/// Parse a URL string, wrapping the error in a [BinError]
+fn parse_url(url: &str) -> Result<Url, BinError> {
+    Url::from_str(url).map_err(|source| BinError::InvalidUrl {
+        url: url.to_string(),
+        source,
+    })
+}
+
 #[repr(u8)]
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
-#[serde(try_from = "u8")]
+#[serde(try_from = "DayValue", into = "u8")]
 #[allow(missing_docs)]
-/// The days of the week for bin collection
+/// The days of the week for bin collection, numbered from Sunday = 0 like the API sends them. Names are
+/// accepted when deserializing too, for bin data cached by older versions.
 pub enum CollectionDay {
     Sunday,
     Monday,
@@ -64,6 +129,47 @@ impl TryFrom<u8> for CollectionDay {
     }
 }
 
+#[derive(Deserialize)]
+#[serde(untagged)]
+/// A day of the week as the API sends it, or as older versions cached it
+enum DayValue {
+    Number(u8),
+    Name(String),
+}
+
+impl TryFrom<DayValue> for CollectionDay {
+    type Error = String;
+    fn try_from(item: DayValue) -> Result<Self, Self::Error> {
+        match item {
+            DayValue::Number(number) => CollectionDay::try_from(number),
+            DayValue::Name(name) => (0..7)
+                .filter_map(|number| CollectionDay::try_from(number).ok())
+                .find(|day| day.to_string().eq_ignore_ascii_case(&name))
+                .ok_or_else(|| format!("Invalid day of the week: {name}")),
+        }
+    }
+}
+
+impl From<CollectionDay> for u8 {
+    fn from(day: CollectionDay) -> Self {
+        day as u8
+    }
+}
+
+impl From<&CollectionDay> for time::Weekday {
+    fn from(day: &CollectionDay) -> Self {
+        match day {
+            CollectionDay::Sunday => time::Weekday::Sunday,
+            CollectionDay::Monday => time::Weekday::Monday,
+            CollectionDay::Tuesday => time::Weekday::Tuesday,
+            CollectionDay::Wednesday => time::Weekday::Wednesday,
+            CollectionDay::Thursday => time::Weekday::Thursday,
+            CollectionDay::Friday => time::Weekday::Friday,
+            CollectionDay::Saturday => time::Weekday::Saturday,
+        }
+    }
+}
+
 impl std::fmt::Display for CollectionDay {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         let day = format!("{self:?}");
@@ -71,7 +177,84 @@ impl std::fmt::Display for CollectionDay {
     }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
+#[serde(from = "String", into = "String")]
+/// The type of event on a bin day. Unknown types are kept as-is, so they serialize back to what the API sent.
+pub enum EventType {
+    /// General waste collection, "waste"
+    Collection,
+    /// Recycling collection, "recycle"
+    Recycling,
+    /// Green waste collection, "organic"
+    GreenWaste,
+    /// Kerbside clean-up, "clean_up"
+    KerbsideCleanup,
+    /// Anything else
+    Other(String),
+}
+
+impl EventType {
+    /// The event type as the API spells it
+    pub fn as_str(&self) -> &str {
+        match self {
+            EventType::Collection => "waste",
+            EventType::Recycling => "recycle",
+            EventType::GreenWaste => "organic",
+            EventType::KerbsideCleanup => "clean_up",
+            EventType::Other(value) => value,
+        }
+    }
+}
+
+impl From<String> for EventType {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "waste" => EventType::Collection,
+            "recycle" => EventType::Recycling,
+            "organic" => EventType::GreenWaste,
+            "clean_up" => EventType::KerbsideCleanup,
+            _ => EventType::Other(value),
+        }
+    }
+}
+
+impl From<EventType> for String {
+    fn from(value: EventType) -> Self {
+        match value {
+            EventType::Other(value) => value,
+            known => known.as_str().to_string(),
+        }
+    }
+}
+
+impl Display for EventType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// (De)serializes a [Date] in the format "YYYY-MM-DD"
+mod ymd {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use time::{format_description::well_known::Iso8601, Date};
+
+    #[allow(clippy::trivially_copy_pass_by_ref)] // serde's `with` needs a reference
+    pub(crate) fn serialize<S: Serializer>(date: &Date, serializer: S) -> Result<S::Ok, S::Error> {
+        let value = date
+            .format(&Iso8601::DATE)
+            .map_err(serde::ser::Error::custom)?;
+        serializer.serialize_str(&value)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Date, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Date::parse(&value, &Iso8601::DATE).map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
 
 /// The locality data
 pub struct Locality {
@@ -93,7 +276,7 @@ pub struct Localities {
     pub localities: Vec<Locality>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 /// The street data
 pub struct Street {
     /// The street ID, used for querying properties
@@ -111,7 +294,7 @@ pub struct Streets {
     pub streets: Vec<Street>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 /// The property data
 pub struct Property {
     /// The property ID, used for querying bin data
@@ -144,28 +327,23 @@ pub struct BinDay {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     /// The bin day color, e.g. "#00FF00"
-    pub color: String,
+    pub color: Rgb,
     /// The bin day text color, e.g. "#000000"
     #[serde(rename = "textColor")]
-    pub text_colour: String,
+    pub text_colour: Rgb,
     /// The bin day border color, e.g. "#FFFFFF"
     #[serde(rename = "borderColor")]
-    pub border_colour: String,
-    /// The bin day start date, e.g. "2024-01-01"
-    pub start: String,
-    /// The bin day event type, e.g. "collection"
-    pub event_type: String,
+    pub border_colour: Rgb,
+    /// The bin day start date, sent as "2024-01-01"
+    #[serde(with = "ymd")]
+    pub start: Date,
+    /// The bin day event type, e.g. "recycle"
+    pub event_type: EventType,
 }
 
 impl std::fmt::Display for BinDay {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(
-            f,
-            "{} ",
-            self.get_start_date()
-                .map(|val| val.to_string())
-                .unwrap_or(self.start.clone())
-        )?;
+        write!(f, "{} ", self.start)?;
         if let Some(name) = &self.name {
             write!(f, "{name} ")?
         }
@@ -173,15 +351,6 @@ impl std::fmt::Display for BinDay {
     }
 }
 
-impl BinDay {
-    /// Get the start date as a `time::Date`, parsing it from the `start` string field. The expected format is "YYYY-MM-DD".
-    pub fn get_start_date(&self) -> Result<Date, String> {
-        let formatter = format_description::parse_owned::<2>("[year]-[month]-[day]")
-            .map_err(|err| err.to_string())?;
-        Date::parse(&self.start, &formatter).map_err(|err| err.to_string())
-    }
-}
-
 #[derive(Serialize, Deserialize, Debug, Clone)]
 /// The bin property data
 pub struct BinProperty {
@@ -240,17 +409,17 @@ impl Display for BinProperty {
 #[allow(missing_docs)]
 pub struct BinData {
     pub property: BinProperty,
-    pub color: String,
+    pub color: Rgb,
     #[serde(rename = "textColor")]
-    pub text_colour: String,
+    pub text_colour: Rgb,
     #[serde(rename = "borderColor")]
-    pub border_colour: String,
+    pub border_colour: Rgb,
 
     pub dow: Vec<CollectionDay>,
     #[serde(rename = "daysOfWeek")]
     pub days_of_week: Vec<CollectionDay>,
     pub start_date: String,
-    pub event_type: String,
+    pub event_type: EventType,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -262,67 +431,189 @@ pub struct ErrorResponse {
     pub status: u16,
 }
 
+#[derive(Debug, Clone)]
+/// The bin data for a property, and when it was fetched
+pub struct PropertySchedule {
+    /// The property data
+    pub bin_data: BinData,
+    /// The bin days in the requested range
+    pub bin_days: Vec<BinDay>,
+    /// When the data was fetched from the API
+    pub fetched: OffsetDateTime,
+    /// True if this is the last known data from the cache, because the API wasn't (or couldn't be) asked
+    pub stale: bool,
+}
+
+impl PropertySchedule {
+    /// How long before `now` the data was fetched, e.g. "3 days ago"
+    pub fn age(&self, now: OffsetDateTime) -> String {
+        let seconds = (now - self.fetched).whole_seconds().max(0);
+        let (count, unit) = match seconds {
+            0..=59 => return "just now".to_string(),
+            60..=3599 => (seconds / 60, "minute"),
+            3600..=86399 => (seconds / 3600, "hour"),
+            _ => (seconds / 86400, "day"),
+        };
+        let plural = if count == 1 { "" } else { "s" };
+        format!("{count} {unit}{plural} ago")
+    }
+}
+
 /// The parsed address data
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct AddressData {
-    /// The street number, e.g. "123"
+    /// The street number, without any unit, e.g. "123", "12A" or "12-14". Empty for a lot without a number.
     pub num: String,
+    /// The unit, flat, apartment or shop number, e.g. "3" in "Unit 3, 12 Smith St" or "3/12 Smith St"
+    pub unit: Option<String>,
+    /// The lot number, e.g. "5" in "Lot 5 Smith St"
+    pub lot: Option<String>,
     /// The street name, without the number, e.g. "drury lane"
     pub street: String,
-    /// The full address, including the number, e.g. "123 drury lane"
+    /// The full address, including the number, e.g. "123 drury lane" or "3/12 smith st"
     pub address: String,
-    /// The suburb, e.g. "brisbane"
+    /// The suburb, without any state or postcode, e.g. "brisbane"
     pub suburb: String,
+    /// The state, if one was given, e.g. "QLD"
+    pub state: Option<String>,
+    /// The postcode, if one was given, e.g. "4000"
+    pub postcode: Option<String>,
+}
+
+impl AddressData {
+    /// The house and/or lot number, for matching against property names
+    pub fn premises(&self) -> Premises {
+        let number = HouseNumber::parse(&self.num).map(|number| HouseNumber {
+            unit: self.unit.clone(),
+            ..number
+        });
+        Premises {
+            number,
+            lot: self.lot.clone(),
+        }
+    }
 }
 
 impl TryFrom<String> for AddressData {
-    type Error = String;
+    type Error = BinError;
 
     fn try_from(value: String) -> Result<Self, Self::Error> {
-        let mut res = value.split(',').map(|s| s.trim());
-        let Some(address) = res.next() else {
-            return Err(
+        let mut parts = value
+            .split(',')
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>();
+        let Some(last) = parts.pop() else {
+            return Err(BinError::InvalidAddress(
                 "No street provided, specify address like 123 drury lane, suburb".to_string(),
-            );
+            ));
         };
-        let mut splitup = address.split_whitespace();
-        let Some(num) = splitup.next() else {
-            return Err(
+        let mut locality = LocalityParts::parse(last);
+        // "12 Smith St, Paddington, QLD 4064" has the state and postcode on their own
+        if locality.suburb.is_empty() {
+            if let Some(suburb) = parts.pop() {
+                let LocalityParts {
+                    suburb,
+                    state,
+                    postcode,
+                } = LocalityParts::parse(suburb);
+                locality = LocalityParts {
+                    suburb,
+                    state: locality.state.or(state),
+                    postcode: locality.postcode.or(postcode),
+                };
+            }
+        }
+        let street_parts = parts;
+        if locality.suburb.is_empty() {
+            return Err(BinError::InvalidAddress(
+                "No suburb provided, specify address like 123 drury lane, suburb".to_string(),
+            ));
+        }
+        if street_parts.is_empty() {
+            // a lone "123 drury lane" is missing its suburb, but a lone "suburb" is missing its street
+            let words = last.split_whitespace().collect::<Vec<_>>();
+            let missing = if Premises::parse(&words).is_some() {
+                "suburb"
+            } else {
+                "street"
+            };
+            return Err(BinError::InvalidAddress(format!(
+                "No {missing} provided, specify address like 123 drury lane, suburb"
+            )));
+        }
+        // "Unit 3, 12 Smith St" splits over two parts, so put them back together
+        let words = street_parts
+            .iter()
+            .flat_map(|part| part.split_whitespace())
+            .collect::<Vec<_>>();
+        let Some((premises, used)) = Premises::parse(&words) else {
+            return Err(BinError::InvalidAddress(
                 "No street number provided, specify address like 123 drury lane, suburb"
                     .to_string(),
-            );
+            ));
         };
 
-        let street = splitup.collect::<Vec<&str>>().join(" ");
-
-        let suburb = match res.next() {
-            Some(s) => s,
-            None => {
-                return Err(
-                    "No suburb provided, specify address like 123 drury lane, suburb".to_string(),
-                )
-            }
+        let street = words.get(used..).unwrap_or_default().join(" ");
+        if street.is_empty() {
+            return Err(BinError::InvalidAddress(
+                "No street name provided, specify address like 123 drury lane, suburb".to_string(),
+            ));
+        }
+        let (num, unit) = match &premises.number {
+            Some(number) => (
+                HouseNumber {
+                    unit: None,
+                    ..number.clone()
+                }
+                .to_string(),
+                number.unit.clone(),
+            ),
+            None => (String::new(), None),
         };
         Ok(AddressData {
-            num: num.to_string(),
-            street: street.to_string(),
-            address: address.to_string(),
-            suburb: suburb.to_string(),
+            num,
+            unit,
+            lot: premises.lot.clone(),
+            address: format!("{premises} {street}"),
+            street,
+            suburb: locality.suburb,
+            state: locality.state,
+            postcode: locality.postcode,
         })
     }
 }
 
-/// The main data structure for interacting with the API
+#[derive(Clone)]
+/// The main data structure for interacting with the API. Clones share the connection pool and rate limiter.
 pub struct BinClient {
     client: reqwest::Client,
     debug: bool,
+    base_url: String,
+    council: Council,
+    clock: Arc<dyn Clock>,
+    cache: Option<Cache>,
+    offline: OfflineMode,
+    retry: RetryPolicy,
+    limiter: Arc<RateLimiter>,
+    user_agent: String,
 }
 
 impl Default for BinClient {
     fn default() -> Self {
+        let council = Council::default();
+        let retry = RetryPolicy::default();
         Self {
-            client: reqwest::Client::new(),
+            client: retry.http_client(),
             debug: false,
+            base_url: council.base_url.clone(),
+            council,
+            clock: Arc::new(SystemClock),
+            cache: None,
+            offline: OfflineMode::Online,
+            retry,
+            limiter: Arc::new(RateLimiter::default()),
+            user_agent: DEFAULT_USER_AGENT.to_string(),
         }
     }
 }
@@ -335,146 +626,582 @@ impl BinClient {
             ..self
         }
     }
-    /// Get the list of localities, which can be queried for streets
-    pub async fn get_localities(&mut self) -> Result<Vec<Locality>, String> {
-        let url = get_url("localities.json");
+
+    /// Builder method, sets the base URL that endpoints are resolved against, e.g. "http://localhost:8080/api/v1/"
+    pub fn with_base_url(self, base_url: &str) -> Result<Self, BinError> {
+        let mut base_url = parse_url(base_url)?.to_string();
+        if !base_url.ends_with('/') {
+            base_url.push('/');
+        }
+        Ok(Self { base_url, ..self })
+    }
+
+    /// Builder method, selects the council to query, which also resets the base URL to the council's
+    pub fn with_council(self, council: Council) -> Self {
+        Self {
+            base_url: council.base_url.clone(),
+            council,
+            ..self
+        }
+    }
+
+    /// Builder method, replaces the source of the current time, e.g. with a [FixedClock] for testing
+    pub fn with_clock(self, clock: impl Clock + 'static) -> Self {
+        Self {
+            clock: Arc::new(clock),
+            ..self
+        }
+    }
+
+    /// Builder method, keeps the locality, street and property lists in `cache`, see [cache]
+    pub fn with_cache(self, cache: Cache) -> Self {
+        Self {
+            cache: Some(cache),
+            ..self
+        }
+    }
+
+    /// Builder method, sets when cached data is used instead of asking the server, see
+    /// [BinClient::with_cache]. Without a cache, [OfflineMode::Offline] fails with [BinError::NotCached].
+    pub fn with_offline(self, offline: OfflineMode) -> Self {
+        Self { offline, ..self }
+    }
+
+    /// Builder method, sets how failed requests are retried and how long each attempt can take, see
+    /// [RetryPolicy]
+    pub fn with_retry(self, retry: RetryPolicy) -> Self {
+        Self {
+            client: retry.http_client(),
+            retry,
+            ..self
+        }
+    }
+
+    /// Builder method, limits how fast and how many requests at once are sent, see [RateLimit]. The
+    /// limiter is shared with clones made after this.
+    pub fn with_rate_limit(self, limit: RateLimit) -> Self {
+        Self {
+            limiter: Arc::new(RateLimiter::new(limit)),
+            ..self
+        }
+    }
+
+    /// Builder method, replaces the User-Agent header, which is [DEFAULT_USER_AGENT] by default
+    pub fn with_user_agent(self, user_agent: &str) -> Self {
+        Self {
+            user_agent: user_agent.to_string(),
+            ..self
+        }
+    }
+
+    /// The rate limiter this client and its clones share
+    pub fn rate_limiter(&self) -> &RateLimiter {
+        &self.limiter
+    }
+
+    /// The response cache, if there is one
+    pub fn cache(&self) -> Option<&Cache> {
+        self.cache.as_ref()
+    }
+
+    /// The clock this client uses to work out what "today" is
+    pub fn clock(&self) -> Arc<dyn Clock> {
+        self.clock.clone()
+    }
+
+    /// Today's date in the council's timezone
+    pub fn today(&self) -> Date {
+        self.clock.today(self.council.timezone)
+    }
+
+    /// The council this client queries
+    pub fn council(&self) -> &Council {
+        &self.council
+    }
+
+    /// The base URL that endpoints are resolved against
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Get the full URL for a given endpoint, resolved against this client's base URL
+    pub fn endpoint_url(&self, filename: &str) -> Result<Url, BinError> {
+        parse_url(&format!("{}{filename}", self.base_url))
+    }
+
+    /// Send a GET request like [BinClient::fetch_once], retrying connection errors, timeouts, 429 and 5xx
+    /// responses according to the [RetryPolicy]. When it gives up after more than one attempt, the error
+    /// lists them all.
+    async fn fetch(
+        &self,
+        url: &Url,
+        context: &str,
+        extra: &[(&str, String)],
+    ) -> Result<(StatusCode, HeaderMap, Vec<u8>), BinError> {
+        let mut attempts = Vec::new();
+        loop {
+            let result = self.fetch_once(url, context, extra).await;
+            let (outcome, retry_after) = match &result {
+                Ok((status, headers, _)) if retry::is_retryable_status(*status) => (
+                    format!("HTTP status {}", status.as_u16()),
+                    retry::retry_after(headers, self.clock.now()),
+                ),
+                Err(BinError::Transport { source, .. }) if retry::is_retryable_error(source) => {
+                    (source.to_string(), None)
+                }
+                _ => return result,
+            };
+
+            let attempt = u32::try_from(attempts.len()).unwrap_or(u32::MAX) + 1;
+            if let Some(delay) = self.retry.delay(attempt, retry_after) {
+                if self.debug {
+                    eprintln!(
+                        "Attempt {attempt} at {url} failed ({outcome}), retrying in {delay:.1?}"
+                    );
+                }
+                attempts.push(Attempt {
+                    outcome,
+                    delay: Some(delay),
+                });
+                tokio::time::sleep(delay).await;
+                continue;
+            }
+
+            if attempts.is_empty() {
+                return result;
+            }
+            attempts.push(Attempt {
+                outcome,
+                delay: None,
+            });
+            let last = match result {
+                Ok((status, _, body)) => self.status_error(url, status, &body),
+                Err(err) => err,
+            };
+            return Err(BinError::RetriesExhausted {
+                attempts,
+                last: Box::new(last),
+            });
+        }
+    }
+
+    /// Send a GET request with the council's headers, and any `extra` ones, returning the status,
+    /// headers and body
+    async fn fetch_once(
+        &self,
+        url: &Url,
+        context: &str,
+        extra: &[(&str, String)],
+    ) -> Result<(StatusCode, HeaderMap, Vec<u8>), BinError> {
         // -H 'Authorization: Token token="<32 hex chars>"' \
         // -H 'Origin: https://impact-apps-calendars.web.app' \
         // -H 'Referer: https://impact-apps-calendars.web.app/' \
-        let response = self
-            .client
-            .get(&url)
+        let mut request = self.client.get(url.clone());
+        // a council that needs its own User-Agent can set it in its headers
+        if !self
+            .council
+            .headers
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case(USER_AGENT.as_str()))
+        {
+            request = request.header(USER_AGENT, &self.user_agent);
+        }
+        for (name, value) in &self.council.headers {
+            request = request.header(name, value);
+        }
+        for (name, value) in extra {
+            request = request.header(*name, value);
+        }
+        // held until the body has been read, so slow responses count towards the concurrency limit
+        let _permit = self.limiter.acquire().await;
+        let response = request
             .send()
             .await
-            .map_err(|err| format!("Failed to query localities {err:?}"))?;
-
-        let localities: Localities = response
-            .json()
+            .map_err(|err| BinError::transport(&format!("query {context}"), err))?;
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response
+            .bytes()
             .await
-            .map_err(|err| format!("Failed to parse localities {err:?}"))?;
+            .map_err(|err| BinError::transport(&format!("read {context} response"), err))?;
+        Ok((status, headers, body.to_vec()))
+    }
+
+    /// The error for a non-success response, the API's own error if it sent one
+    fn status_error(&self, url: &Url, status: StatusCode, body: &[u8]) -> BinError {
+        if self.debug {
+            eprintln!("{url} returned {status}: {}", String::from_utf8_lossy(body));
+        }
+        if let Ok(error_response) = serde_json::from_slice::<ErrorResponse>(body) {
+            return BinError::Api(error_response);
+        }
+        BinError::HttpStatus {
+            url: url.to_string(),
+            status: status.as_u16(),
+        }
+    }
+
+    /// Send a GET request and deserialize the JSON response, turning non-success statuses into errors
+    async fn get_json<T: DeserializeOwned>(&self, url: Url, context: &str) -> Result<T, BinError> {
+        let (status, _, body) = self.fetch(&url, context, &[]).await?;
+        if !status.is_success() {
+            return Err(self.status_error(&url, status, &body));
+        }
+        serde_json::from_slice(&body).map_err(|err| BinError::json(context, err))
+    }
+
+    /// Like [BinClient::get_json], but using the cache if there is one. Entries younger than `ttl` are
+    /// used without asking the server, older ones are revalidated with their ETag or Last-Modified date.
+    async fn get_cached_json<T: DeserializeOwned>(
+        &self,
+        url: Url,
+        context: &str,
+        ttl: Duration,
+    ) -> Result<T, BinError> {
+        let offline = self.offline == OfflineMode::Offline;
+        let Some(cache) = &self.cache else {
+            if offline {
+                return Err(BinError::NotCached(context.to_string()));
+            }
+            return self.get_json(url, context).await;
+        };
+        let now = self.clock.now();
+        let cached = cache.load(url.as_str());
+        if let Some(entry) = &cached {
+            if offline || (!cache.refresh() && entry.is_fresh(now, ttl)) {
+                if let Ok(value) = serde_json::from_str(&entry.body) {
+                    if self.debug {
+                        eprintln!("Using cached {context} for {url}");
+                    }
+                    return Ok(value);
+                }
+            }
+        }
 
+        if offline {
+            return Err(BinError::NotCached(context.to_string()));
+        }
+
+        let mut extra = Vec::new();
+        if let Some(entry) = &cached {
+            if let Some(etag) = &entry.etag {
+                extra.push((IF_NONE_MATCH.as_str(), etag.clone()));
+            }
+            if let Some(last_modified) = &entry.last_modified {
+                extra.push((IF_MODIFIED_SINCE.as_str(), last_modified.clone()));
+            }
+        }
+        let response = self.fetch(&url, context, &extra).await.and_then(
+            |(status, headers, body)| match &cached {
+                Some(entry) if status == StatusCode::NOT_MODIFIED => Ok(CacheEntry {
+                    fetched: now.unix_timestamp(),
+                    ..entry.clone()
+                }),
+                _ if !status.is_success() => Err(self.status_error(&url, status, &body)),
+                _ => {
+                    let header = |name| {
+                        headers
+                            .get(name)
+                            .and_then(|value: &HeaderValue| value.to_str().ok())
+                            .map(str::to_string)
+                    };
+                    Ok(CacheEntry {
+                        fetched: now.unix_timestamp(),
+                        etag: header(ETAG),
+                        last_modified: header(LAST_MODIFIED),
+                        body: String::from_utf8_lossy(&body).into_owned(),
+                    })
+                }
+            },
+        );
+        let entry = match response {
+            Ok(entry) => entry,
+            Err(err) => {
+                let fallback = self.offline == OfflineMode::Fallback && err.is_unavailable();
+                return match cached.filter(|_| fallback) {
+                    Some(entry) => {
+                        if self.debug {
+                            eprintln!("{err}, using cached {context} for {url}");
+                        }
+                        serde_json::from_str(&entry.body)
+                            .map_err(|err| BinError::json(context, err))
+                    }
+                    None => Err(err),
+                };
+            }
+        };
+        // parse before storing, so a bad response isn't kept around
+        let value =
+            serde_json::from_str(&entry.body).map_err(|err| BinError::json(context, err))?;
+        if let Err(err) = cache.store(url.as_str(), &entry) {
+            if self.debug {
+                eprintln!(
+                    "Failed to cache {context} in {}: {err}",
+                    cache.dir().display()
+                );
+            }
+        }
+        Ok(value)
+    }
+
+    /// Get the list of localities, which can be queried for streets
+    pub async fn get_localities(&mut self) -> Result<Vec<Locality>, BinError> {
+        let url = self.endpoint_url("localities.json")?;
+        let localities: Localities = self
+            .get_cached_json(url, "localities", cache::LOCALITIES_TTL)
+            .await?;
         Ok(localities.localities)
     }
 
     /// Get the list of streets for a given locality, which can be queried for properties
-    pub async fn get_streets(&mut self, locality_id: &u32) -> Result<Vec<Street>, String> {
-        let mut url = Url::from_str(&get_url("streets.json"))
-            .map_err(|err| format!("Failed to make streets URL {err:?}"))?;
+    pub async fn get_streets(&mut self, locality_id: &u32) -> Result<Vec<Street>, BinError> {
+        let mut url = self.endpoint_url("streets.json")?;
         url.query_pairs_mut()
             .append_pair("locality", &locality_id.to_string());
-        let response = self
-            .client
-            .get(url.as_str())
-            .send()
-            .await
-            .map_err(|err| format!("Failed to query streets {err:?}"))?;
-
-        let streets: Streets = response
-            .json()
-            .await
-            .map_err(|err| format!("Failed to parse streets JSON {err:?}"))?;
-
+        let streets: Streets = self
+            .get_cached_json(url, "streets", cache::STREETS_TTL)
+            .await?;
         Ok(streets.streets)
     }
 
     /// Get the list of properties for a given street, which can be queried for bin data
-    pub async fn get_properties(&mut self, street_id: &u32) -> Result<Vec<Property>, String> {
-        let mut url = reqwest::Url::from_str(&get_url("properties.json"))
-            .map_err(|err| format!("Failed to make properties URL {err:?}"))?;
+    pub async fn get_properties(&mut self, street_id: &u32) -> Result<Vec<Property>, BinError> {
+        let mut url = self.endpoint_url("properties.json")?;
         url.query_pairs_mut()
             .append_pair("street", &street_id.to_string());
-        let response = self
-            .client
-            .get(url.as_str())
-            .send()
-            .await
-            .map_err(|err| format!("Failed to query properties {err:?}"))?;
-
-        let properties: Properties = response
-            .json()
-            .await
-            .map_err(|err| format!("Failed to parse properties JSON {err:?}"))?;
-
+        let properties: Properties = self
+            .get_cached_json(url, "properties", cache::PROPERTIES_TTL)
+            .await?;
         Ok(properties.properties)
     }
 
-    /// Get the bin data for a given property ID, including the property data and the associated bin days
+    /// Get the bin data for a given property ID, including the property data and the associated bin days.
+    ///
+    /// Covers the current month, see [BinClient::get_property_range] to pick the dates.
     pub async fn get_property(
         &mut self,
         property_id: u64,
-    ) -> Result<(BinData, Vec<BinDay>), String> {
-        let mut url = Url::from_str(&get_url(&format!("properties/{property_id}.json")))
-            .map_err(|err| format!("Failed to create property URL! {err:?}"))?;
+    ) -> Result<(BinData, Vec<BinDay>), BinError> {
+        let (start, end) = month_window(self.today())?;
+        self.get_property_range(property_id, start, end).await
+    }
+
+    /// Get the bin data for a given property ID, with the bin days between `start` and `end` (inclusive).
+    ///
+    /// Ranges longer than [MAX_QUERY_DAYS] are split into multiple requests and merged. Cached data is
+    /// used according to the client's [OfflineMode], see [BinClient::get_property_schedule] to find out
+    /// whether it was.
+    pub async fn get_property_range(
+        &mut self,
+        property_id: u64,
+        start: Date,
+        end: Date,
+    ) -> Result<(BinData, Vec<BinDay>), BinError> {
+        let schedule = self.get_property_schedule(property_id, start, end).await?;
+        Ok((schedule.bin_data, schedule.bin_days))
+    }
+
+    /// Like [BinClient::get_property_range], but also says when the data was fetched. Each successful
+    /// response is kept in the cache, joined with the dates already cached, and returned (marked as
+    /// stale) in [OfflineMode::Offline], or in [OfflineMode::Fallback] when the API can't be reached.
+    /// The cache is only used if it covers every date from `start` to `end`.
+    pub async fn get_property_schedule(
+        &mut self,
+        property_id: u64,
+        start: Date,
+        end: Date,
+    ) -> Result<PropertySchedule, BinError> {
+        self.get_schedule_or_cached(property_id, start, end, |cached| {
+            cached.covers(start, end).then_some(end)
+        })
+        .await
+    }
+
+    /// [BinClient::get_property_schedule], but `usable_until` decides how much of the cached data can be
+    /// used instead, returning the last date to use (at most `end`), or `None` if it won't do
+    pub(crate) async fn get_schedule_or_cached(
+        &mut self,
+        property_id: u64,
+        start: Date,
+        end: Date,
+        usable_until: impl Fn(&CachedSchedule) -> Option<Date>,
+    ) -> Result<PropertySchedule, BinError> {
+        if start > end {
+            return Err(BinError::InvalidDateRange { start, end });
+        }
+        let key = self
+            .endpoint_url(&format!("properties/{property_id}.json"))?
+            .to_string();
+
+        let err = match self.offline {
+            OfflineMode::Offline => BinError::NotCached(format!(
+                "bin data for property {property_id} from {start} to {end}"
+            )),
+            OfflineMode::Online | OfflineMode::Fallback => {
+                match self.fetch_property_range(property_id, start, end).await {
+                    Ok((bin_data, bin_days)) => {
+                        let fetched = self.clock.now();
+                        if let Some(cache) = &self.cache {
+                            let cached = CachedSchedule::new(
+                                fetched.unix_timestamp(),
+                                start,
+                                end,
+                                bin_data.clone(),
+                                bin_days.clone(),
+                            );
+                            let cached = match cache.load_schedule(&key) {
+                                Some(older) => older.merge(cached),
+                                None => cached,
+                            };
+                            if let Err(err) = cache.store_schedule(&key, &cached) {
+                                if self.debug {
+                                    eprintln!(
+                                        "Failed to cache bin data in {}: {err}",
+                                        cache.dir().display()
+                                    );
+                                }
+                            }
+                        }
+                        return Ok(PropertySchedule {
+                            bin_data,
+                            bin_days,
+                            fetched,
+                            stale: false,
+                        });
+                    }
+                    Err(err) if self.offline == OfflineMode::Fallback && err.is_unavailable() => {
+                        err
+                    }
+                    Err(err) => return Err(err),
+                }
+            }
+        };
+
+        let Some((cached, end)) = self
+            .cache
+            .as_ref()
+            .and_then(|cache| cache.load_schedule(&key))
+            .and_then(|cached| usable_until(&cached).map(|until| (cached, until.min(end))))
+        else {
+            return Err(err);
+        };
+        if self.debug {
+            eprintln!("{err}, using the bin data cached for property {property_id} up to {end}");
+        }
+        let fetched =
+            OffsetDateTime::from_unix_timestamp(cached.fetched(start, end).unwrap_or_default())
+                .map_err(|err| BinError::date("read cached fetch time", err))?;
+        let bin_days = cached
+            .bin_days
+            .into_iter()
+            .filter(|day| (start..=end).contains(&day.start))
+            .collect();
+        Ok(PropertySchedule {
+            bin_data: cached.bin_data,
+            bin_days,
+            fetched,
+            stale: true,
+        })
+    }
+
+    /// Query the API for the bin data between `start` and `end`, splitting it into windows the API will answer
+    async fn fetch_property_range(
+        &mut self,
+        property_id: u64,
+        start: Date,
+        end: Date,
+    ) -> Result<(BinData, Vec<BinDay>), BinError> {
+        if start > end {
+            return Err(BinError::InvalidDateRange { start, end });
+        }
 
-        let mut url_query = url.query_pairs_mut();
+        let mut result: Option<(BinData, Vec<BinDay>)> = None;
+        let mut chunk_start = start;
+        while chunk_start <= end {
+            let chunk_end = chunk_start
+                .checked_add(time::Duration::days(MAX_QUERY_DAYS - 1))
+                .map_or(end, |val| val.min(end));
+
+            let (bin_data, bin_days) = self
+                .get_property_window(property_id, chunk_start, chunk_end)
+                .await?;
+            match result.as_mut() {
+                None => result = Some((bin_data, bin_days)),
+                Some((_, all_days)) => {
+                    for day in bin_days {
+                        if !all_days.iter().any(|d| {
+                            d.start == day.start
+                                && d.name == day.name
+                                && d.event_type == day.event_type
+                        }) {
+                            all_days.push(day);
+                        }
+                    }
+                }
+            }
+
+            chunk_start = match chunk_end.next_day() {
+                Some(val) => val,
+                None => break,
+            };
+        }
+
+        result.ok_or(BinError::InvalidDateRange { start, end })
+    }
+
+    /// Query the API for a single window of dates, which must be short enough for the API to answer
+    async fn get_property_window(
+        &mut self,
+        property_id: u64,
+        start: Date,
+        end: Date,
+    ) -> Result<(BinData, Vec<BinDay>), BinError> {
+        let mut url = self.endpoint_url(&format!("properties/{property_id}.json"))?;
 
         let date_formatter = format_description::parse_borrowed::<2>(
             "[year]-[month]-[day]T[hour]:[minute]:[second]Z",
         )
-        .map_err(|err| format!("Failed to generate date formatter! {err:?}"))?;
-
-        // get the last day of the previous month
-        let start = time::OffsetDateTime::now_utc()
-            - time::Duration::days(time::OffsetDateTime::now_utc().day() as i64);
-        let start = start.replace_time(
-            time::Time::from_hms(14, 0, 0)
-                .map_err(|err| format!("Failed to generate end date! {err:?}"))?,
-        );
+        .map_err(|err| BinError::date("generate date formatter", err))?;
 
-        let start = start
+        // the API wants UTC timestamps, so send local midnight at each end of the range
+        let start = window_boundary(start, self.council.timezone)
             .format(&date_formatter)
-            .map_err(|err| format!("failed to string-format start date! {err:?}"))?;
+            .map_err(|err| BinError::date("string-format start date", err))?;
         if self.debug {
             eprintln!("Start date: {start}");
         }
 
-        let end = time::OffsetDateTime::now_utc();
-        let days_of_month = days_in_month(end.month(), end.year());
-        let end = end.replace_date(
-            time::Date::from_calendar_date(end.year(), end.month(), days_of_month)
-                .map_err(|err| format!("Failed to calculate end date! {err:?}"))?,
-        );
-        let end = end
-            .format(&date_formatter)
-            .map_err(|err| format!("Failed to string-format end date! {err:?}"))?;
+        let end = window_boundary(
+            end.next_day()
+                .ok_or(BinError::InvalidDateRange { start: end, end })?,
+            self.council.timezone,
+        )
+        .format(&date_formatter)
+        .map_err(|err| BinError::date("string-format end date", err))?;
         if self.debug {
             eprintln!("End date: {end}");
         }
 
-        url_query.append_pair("start", &start);
-        url_query.append_pair("end", &end);
-        drop(url_query);
+        url.query_pairs_mut()
+            .append_pair("start", &start)
+            .append_pair("end", &end);
         if self.debug {
             eprintln!("get_property URL: {url}");
         }
 
-        let response = self
-            .client
-            .get(url)
-            .send()
-            .await
-            .map_err(|err| format!("Failed to query bin data for property {err:?}"))?;
-
-        if response.status() == reqwest::StatusCode::NOT_FOUND {
-            if self.debug {
-                eprintln!(
-                    "Property with ID {property_id} not found: {:?}",
-                    response.json::<Value>().await
-                );
+        let data: Value = match self.get_json(url, "bin data for property").await {
+            Ok(val) => val,
+            Err(BinError::HttpStatus { status: 404, .. }) => {
+                return Err(BinError::PropertyNotFound(property_id.to_string()))
             }
-            return Err(format!("Property with ID {property_id} not found"));
-        }
-
-        let data: Value = response
-            .json()
-            .await
-            .map_err(|err| format!("Failed to parse bin data for property from JSON {err:?}"))?;
+            Err(BinError::Api(error_response)) if error_response.status == 404 => {
+                return Err(BinError::PropertyNotFound(property_id.to_string()))
+            }
+            Err(err) => return Err(err),
+        };
 
         if let Ok(error_response) = serde_json::from_value::<ErrorResponse>(data.clone()) {
-            return Err(format!(
-                "API returned an error for property {property_id}: {} (status {})",
-                error_response.error, error_response.status
-            ));
+            return Err(BinError::Api(error_response));
         }
 
         if let Some(data) = data.as_array() {
@@ -482,14 +1209,14 @@ impl BinClient {
             let bin_property: Value = match data.next() {
                 Some(val) => val.clone(),
                 None => {
-                    return Err(
+                    return Err(BinError::InvalidResponse(
                         "Failed to find the first result, which should be a property!".to_string(),
-                    )
+                    ))
                 }
             };
 
             let mut bin_property: BinData = serde_json::from_value(bin_property)
-                .map_err(|err| format!("Failed to parse property data! {err:?}"))?;
+                .map_err(|err| BinError::json("property data", err))?;
 
             bin_property.property.id = Some(property_id);
 
@@ -498,15 +1225,15 @@ impl BinClient {
             for day in data {
                 bin_days.push(
                     serde_json::from_value(day.clone())
-                        .map_err(|err| format!("Failed to parse a bin day! {err:?}"))?,
+                        .map_err(|err| BinError::json("bin day", err))?,
                 );
             }
 
             Ok((bin_property, bin_days))
         } else {
-            Err(format!(
-                "Failed to parse bin data for property from JSON: expected an array: {data:?}"
-            ))
+            Err(BinError::InvalidResponse(format!(
+                "expected an array of bin data for property {property_id}: {data:?}"
+            )))
         }
     }
 
@@ -514,70 +1241,951 @@ impl BinClient {
     pub async fn get_address(
         &mut self,
         address: AddressData,
-    ) -> Result<(BinData, Vec<BinDay>), String> {
-        let localities = match self.get_localities().await {
-            Ok(val) => val,
-            Err(e) => {
-                eprintln!("Failed to query localities: {e}");
-                return Err(format!("Failed to query localities: {e}"));
-            }
-        };
+    ) -> Result<(BinData, Vec<BinDay>), BinError> {
+        let property = self.find_property(address).await?;
+        self.get_property(property.id).await
+    }
 
-        let my_locality = match localities
-            .iter()
-            .find(|l| l.name.to_lowercase() == address.suburb.to_lowercase())
-        {
-            Some(l) => l,
-            None => {
-                return Err(format!("Suburb {} not found", address.suburb));
-            }
+    /// Get the localities for our council. If the server knows about more than one council, only ours
+    /// are returned, so identically named suburbs don't get mixed up. Otherwise we trust the server is
+    /// only serving us.
+    pub async fn get_council_localities(&mut self) -> Result<Vec<Locality>, BinError> {
+        let mut localities = self.get_localities().await?;
+        if localities.iter().any(|l| self.council.matches_locality(l)) {
+            localities.retain(|l| self.council.matches_locality(l));
+        }
+        Ok(localities)
+    }
+
+    /// Find a locality by name, ignoring case and punctuation. If there's no match the error suggests
+    /// the closest names, see [matching::rank].
+    ///
+    /// With a postcode, it picks between localities with the same name, and errors if the name is
+    /// only found in other postcodes. Localities the API doesn't give a postcode for always agree.
+    pub async fn find_locality(
+        &mut self,
+        suburb: &str,
+        postcode: Option<&str>,
+    ) -> Result<Locality, BinError> {
+        let mut localities = self.get_council_localities().await?;
+        let index = {
+            let ranked = matching::rank(suburb, &localities, |l| &l.name);
+            let named = ranked
+                .iter()
+                .take_while(|r| r.distance == 0)
+                .collect::<Vec<_>>();
+            let chosen = match postcode {
+                Some(postcode) => {
+                    let agrees = |l: &Locality| l.postcode.as_deref().is_none_or(|p| p == postcode);
+                    match named.iter().find(|r| agrees(r.item)) {
+                        Some(r) => Some(r.index),
+                        None if !named.is_empty() => {
+                            return Err(BinError::PostcodeMismatch {
+                                suburb: suburb.to_string(),
+                                postcode: postcode.to_string(),
+                                expected: named
+                                    .iter()
+                                    .filter_map(|r| r.item.postcode.clone())
+                                    .collect(),
+                            })
+                        }
+                        None => None,
+                    }
+                }
+                None => named.first().map(|r| r.index),
+            };
+            let Some(index) = chosen else {
+                // the localities in the postcode are the best guesses, then the similar names
+                let mut suggestions = localities
+                    .iter()
+                    .filter(|l| postcode.is_some() && l.postcode.as_deref() == postcode)
+                    .map(|l| l.name.clone())
+                    .take(matching::MAX_SUGGESTIONS)
+                    .collect::<Vec<_>>();
+                for name in matching::suggestions(&ranked, |l| &l.name) {
+                    if suggestions.len() < matching::MAX_SUGGESTIONS && !suggestions.contains(&name)
+                    {
+                        suggestions.push(name);
+                    }
+                }
+                return Err(BinError::LocalityNotFound {
+                    name: suburb.to_string(),
+                    suggestions,
+                });
+            };
+            index
         };
+        let my_locality = localities.swap_remove(index);
         if self.debug {
             println!("{my_locality:?}");
         }
+        Ok(my_locality)
+    }
 
-        let streets = self
-            .get_streets(&my_locality.id)
-            .await
-            .map_err(|e| format!("Failed to query streets: {e}"))?;
-
-        let my_street: Street = match streets
-            .into_iter()
-            .find(|s| s.name.to_lowercase() == address.street.to_lowercase())
-        {
-            Some(s) => s,
-            None => {
-                return Err(format!("Street '{}' not found", address.street));
+    /// Find a street by name in a locality, ignoring case and punctuation, and treating street types
+    /// and their abbreviations (e.g. "Street" and "St") the same
+    pub async fn find_street(
+        &mut self,
+        street: &str,
+        suburb: &str,
+        postcode: Option<&str>,
+    ) -> Result<Street, BinError> {
+        let my_locality = self.find_locality(suburb, postcode).await?;
+        let mut streets = self.get_streets(&my_locality.id).await?;
+        let ranked = matching::rank(street, &streets, |s| &s.name);
+        let index = match ranked.first() {
+            Some(best) if best.distance == 0 => best.index,
+            _ => {
+                return Err(BinError::StreetNotFound {
+                    name: street.to_string(),
+                    suggestions: matching::suggestions(&ranked, |s| &s.name),
+                })
             }
         };
-        let properties = self
-            .get_properties(&my_street.id)
-            .await
-            .map_err(|e| format!("Failed to query properties: {e}"))?;
-
-        let my_property = match properties.iter().find(|p| {
-            p.name
-                .to_lowercase()
-                .contains(&address.address.to_lowercase())
-        }) {
-            Some(p) => p,
-            None => {
-                return Err(format!("Property {} not found", address.address));
+        Ok(streets.swap_remove(index))
+    }
+
+    /// Look up the property for a given address, walking the locality, street and property lists
+    pub async fn find_property(&mut self, address: AddressData) -> Result<Property, BinError> {
+        let my_street = self
+            .find_street(
+                &address.street,
+                &address.suburb,
+                address.postcode.as_deref(),
+            )
+            .await?;
+        let properties = self.get_properties(&my_street.id).await?;
+
+        let wanted = address.premises();
+        let (mut exact, mut range, mut any_unit): (Vec<Property>, Vec<Property>, Vec<Property>) =
+            (Vec::new(), Vec::new(), Vec::new());
+        for property in properties {
+            let Some(name) = PropertyName::parse(&property.name) else {
+                continue;
+            };
+            match address::match_premises(&wanted, &name.premises) {
+                NumberMatch::Exact => exact.push(property),
+                NumberMatch::Range => range.push(property),
+                NumberMatch::AnyUnit => any_unit.push(property),
+                NumberMatch::None => {}
             }
+        }
+        // an exact match beats a range that includes the number, which beats the units at the number
+        let mut candidates = [exact, range, any_unit]
+            .into_iter()
+            .find(|candidates| !candidates.is_empty())
+            .unwrap_or_default();
+        if candidates.len() > 1 {
+            return Err(BinError::AmbiguousAddress {
+                address: address.address,
+                candidates,
+            });
+        }
+        let Some(my_property) = candidates.pop() else {
+            return Err(BinError::PropertyNotFound(address.address));
         };
         if self.debug {
             println!("{my_property:?}");
         }
-
-        self.get_property(my_property.id)
-            .await
-            .map_err(|e| format!("Failed to get property: {e}"))
+        Ok(my_property)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::fixtures;
+    use serde_json::json;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// A stub of the API, serving a single property at "2 Boundary St, West End"
+    pub(crate) async fn stub_server() -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/localities.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "localities": [
+                    // same name, different council, so get_address has to filter by council
+                    {"id": 3, "name": "West End", "postcode": "4810", "council": "Townsville City Council"},
+                    {"id": 1, "name": "Highgate Hill", "postcode": "4101", "council": "Brisbane"},
+                    {"id": 2, "name": "West End", "postcode": "4101", "council": "Brisbane"},
+                ]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/streets.json"))
+            .and(query_param("locality", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "streets": [{"id": 20, "name": "Boundary St", "locality": "West End"}]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/properties.json"))
+            .and(query_param("street", "20"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "properties": [
+                    // listed before the real one, so substring matching would pick it
+                    {"id": 2695612, "name": "12 Boundary St", "zone": "Zone 1", "voucher_preferences": 0},
+                    {"id": 2695626, "name": "2 Boundary St", "zone": "Zone 1", "voucher_preferences": 0},
+                    {"id": 2695624, "name": "2-4 Boundary St", "zone": "Zone 1", "voucher_preferences": 0},
+                    {"id": 2695701, "name": "1/22 Boundary St", "zone": "Zone 1", "voucher_preferences": 0},
+                    {"id": 2695702, "name": "2/22 Boundary St", "zone": "Zone 1", "voucher_preferences": 0},
+                    {"id": 2695805, "name": "Lot 5 Boundary St", "zone": "Zone 1", "voucher_preferences": 0},
+                ]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/properties/2695626.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+                fixtures::bin_data_json(),
+                fixtures::recycling_json("2024-01-09"),
+            ])))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/properties/1.json"))
+            .respond_with(ResponseTemplate::new(404).set_body_json(json!({
+                "error": "Not found", "status": 404
+            })))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    pub(crate) fn stub_client(server: &MockServer) -> BinClient {
+        // no need to be polite to a local stub
+        BinClient::default()
+            .with_debug()
+            .with_rate_limit(RateLimit {
+                requests_per_second: 0.0,
+                ..RateLimit::default()
+            })
+            .with_base_url(&format!("{}/api/v1", server.uri()))
+            .expect("Failed to set base URL")
+    }
+
+    #[test]
+    fn test_bin_day_round_trip() {
+        let raw = json!({
+            "name": "Recycling",
+            "color": "#FFD700",
+            "textColor": "#000",
+            "borderColor": "#ffd700",
+            "start": "2024-01-09",
+            "event_type": "recycle",
+        });
+        let day: BinDay = serde_json::from_value(raw.clone()).expect("Failed to parse bin day");
+        assert_eq!(day.start.to_string(), "2024-01-09");
+        assert_eq!(day.event_type, EventType::Recycling);
+        assert_eq!(day.color, Rgb::new(0xff, 0xd7, 0x00));
+        assert_eq!(
+            serde_json::to_value(&day).expect("Failed to serialize"),
+            raw
+        );
+
+        let raw = json!({
+            "color": "#FFD700",
+            "textColor": "#000",
+            "borderColor": "#ffd700",
+            "start": "2024-01-09",
+            "event_type": "something_new",
+        });
+        let day: BinDay = serde_json::from_value(raw.clone()).expect("Failed to parse bin day");
+        assert_eq!(
+            day.event_type,
+            EventType::Other("something_new".to_string())
+        );
+        assert_eq!(
+            serde_json::to_value(&day).expect("Failed to serialize"),
+            raw
+        );
+
+        let mut raw = raw;
+        raw["color"] = json!("yellow");
+        assert!(serde_json::from_value::<BinDay>(raw).is_err());
+    }
+
+    #[test]
+    fn test_bin_data_round_trip() {
+        let mut raw = fixtures::bin_data_json();
+        raw["property"]["collection_day_2"] = json!(5);
+        raw["dow"] = json!([2, 5]);
+        raw["daysOfWeek"] = json!([2, 5]);
+        let bin_data: BinData =
+            serde_json::from_value(raw.clone()).expect("Failed to parse bin data");
+        assert_eq!(bin_data.property.collection_day, CollectionDay::Tuesday);
+        assert_eq!(
+            serde_json::to_value(&bin_data).expect("Failed to serialize"),
+            raw
+        );
+    }
+
+    #[test]
+    fn test_month_window() {
+        let today = Date::from_calendar_date(2024, time::Month::February, 10).expect("valid date");
+        let (start, end) = month_window(today).expect("Failed to get window");
+        assert_eq!(start.to_string(), "2024-02-01");
+        assert_eq!(end.to_string(), "2024-02-29");
+    }
+
+    /// A clock stuck at the given UTC time
+    pub(crate) fn clock_at(year: i32, month: time::Month, day: u8, hour: u8) -> FixedClock {
+        FixedClock(
+            Date::from_calendar_date(year, month, day)
+                .and_then(|date| date.with_hms(hour, 0, 0))
+                .expect("valid date")
+                .assume_utc(),
+        )
+    }
+
+    /// 01:00 on the 1st of April in Brisbane, but still March in UTC
+    fn brisbane_april_fools() -> FixedClock {
+        clock_at(2024, time::Month::March, 31, 15)
+    }
+
+    /// Checks the default query window for a client whose clock is stuck at `clock`
+    fn assert_window(clock: FixedClock, today: &str, start: &str, end: &str) {
+        let client = BinClient::default().with_clock(clock);
+        assert_eq!(client.today().to_string(), today);
+        let (window_start, window_end) =
+            month_window(client.today()).expect("Failed to get window");
+        let timezone = client.council().timezone;
+        assert_eq!(window_boundary(window_start, timezone).to_string(), start);
+        assert_eq!(
+            window_boundary(window_end.next_day().expect("valid date"), timezone).to_string(),
+            end
+        );
+    }
+
+    #[test]
+    fn test_window_first_day_of_month() {
+        // 00:00 on the 1st in Brisbane is still the previous month in UTC
+        assert_window(
+            clock_at(2024, time::Month::May, 31, 14),
+            "2024-06-01",
+            "2024-05-31 14:00:00.0 +00:00:00",
+            "2024-06-30 14:00:00.0 +00:00:00",
+        );
+    }
+
+    #[test]
+    fn test_window_last_day_of_month() {
+        assert_window(
+            clock_at(2024, time::Month::June, 30, 13),
+            "2024-06-30",
+            "2024-05-31 14:00:00.0 +00:00:00",
+            "2024-06-30 14:00:00.0 +00:00:00",
+        );
+    }
+
+    #[test]
+    fn test_window_leap_year() {
+        assert_window(
+            clock_at(2024, time::Month::February, 28, 20),
+            "2024-02-29",
+            "2024-01-31 14:00:00.0 +00:00:00",
+            "2024-02-29 14:00:00.0 +00:00:00",
+        );
+        assert_window(
+            clock_at(2023, time::Month::February, 28, 13),
+            "2023-02-28",
+            "2023-01-31 14:00:00.0 +00:00:00",
+            "2023-02-28 14:00:00.0 +00:00:00",
+        );
+    }
+
+    #[test]
+    fn test_window_year_rollover() {
+        assert_window(
+            clock_at(2024, time::Month::December, 31, 14),
+            "2025-01-01",
+            "2024-12-31 14:00:00.0 +00:00:00",
+            "2025-01-31 14:00:00.0 +00:00:00",
+        );
+        assert_window(
+            clock_at(2024, time::Month::December, 31, 13),
+            "2024-12-31",
+            "2024-11-30 14:00:00.0 +00:00:00",
+            "2024-12-31 14:00:00.0 +00:00:00",
+        );
+    }
+
+    #[tokio::test]
+    async fn test_today_is_local() {
+        let server = stub_server().await;
+        let mut client = stub_client(&server).with_clock(brisbane_april_fools());
+        assert_eq!(client.today().to_string(), "2024-04-01");
+
+        client
+            .get_property(2695626)
+            .await
+            .expect("Failed to get property");
+        let requests = server
+            .received_requests()
+            .await
+            .expect("Request recording is enabled");
+        let query = requests
+            .last()
+            .map(|req| req.url.query().unwrap_or_default().to_string())
+            .expect("Should have made a request");
+        assert_eq!(
+            query,
+            "start=2024-03-31T14%3A00%3A00Z&end=2024-04-30T14%3A00%3A00Z"
+        );
+
+        let client = stub_client(&server)
+            .with_clock(brisbane_april_fools())
+            .with_council(Council::brisbane().with_timezone(UtcOffset::UTC));
+        assert_eq!(client.today().to_string(), "2024-03-31");
+    }
+
+    #[tokio::test]
+    async fn test_property_range_is_split() {
+        let server = stub_server().await;
+        let mut client = stub_client(&server);
+
+        let start = Date::from_calendar_date(2024, time::Month::January, 1).expect("valid date");
+        let end = Date::from_calendar_date(2024, time::Month::April, 30).expect("valid date");
+        let (_, bin_days) = client
+            .get_property_range(2695626, start, end)
+            .await
+            .expect("Failed to get property range");
+        // the same stub day comes back for each chunk, it should only be listed once
+        assert_eq!(bin_days.len(), 1);
+
+        let requests = server
+            .received_requests()
+            .await
+            .expect("Request recording is enabled");
+        let starts = requests
+            .iter()
+            .filter_map(|req| {
+                req.url
+                    .query_pairs()
+                    .find(|(key, _)| key == "start")
+                    .map(|(_, val)| val.to_string())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(starts, vec!["2023-12-31T14:00:00Z", "2024-03-02T14:00:00Z"]);
+
+        assert!(matches!(
+            client.get_property_range(2695626, end, start).await,
+            Err(BinError::InvalidDateRange { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_stub_lookup_chain() {
+        let server = stub_server().await;
+        let mut client = stub_client(&server);
+        assert!(client.base_url().ends_with("/api/v1/"));
+
+        let address = AddressData::try_from("2 Boundary St, West End".to_string())
+            .expect("Failed to parse address");
+        let (bin_data, bin_days) = client
+            .get_address(address)
+            .await
+            .expect("Failed to get address");
+        assert_eq!(bin_data.property.id, Some(2695626));
+        assert_eq!(bin_days.len(), 1);
+
+        let err = client
+            .get_property(1)
+            .await
+            .expect_err("Property 1 shouldn't exist");
+        assert!(matches!(err, BinError::PropertyNotFound(_)));
+
+        let address = AddressData::try_from("2 Boundary St, Nowhere".to_string())
+            .expect("Failed to parse address");
+        let err = client
+            .get_address(address)
+            .await
+            .expect_err("Suburb shouldn't exist");
+        assert!(matches!(err, BinError::LocalityNotFound { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_stub_discovery() {
+        let server = stub_server().await;
+        let mut client = stub_client(&server);
+
+        let localities = client
+            .get_council_localities()
+            .await
+            .expect("Failed to get localities");
+        assert_eq!(localities.len(), 2);
+        assert!(localities.iter().all(|l| l.council == "Brisbane"));
+
+        let street = client
+            .find_street("boundary st", "west end", None)
+            .await
+            .expect("Failed to find street");
+        assert_eq!(street.name, "Boundary St");
+
+        let street = client
+            .find_street("Boundary Street", "West-End", None)
+            .await
+            .expect("Failed to find street with the type spelt out");
+        assert_eq!(street.name, "Boundary St");
+
+        let err = client
+            .find_street("Boundry St", "West End", None)
+            .await
+            .expect_err("Street shouldn't exist");
+        match err {
+            BinError::StreetNotFound { suggestions, .. } => {
+                assert_eq!(suggestions, vec!["Boundary St".to_string()]);
+            }
+            other => panic!("Expected StreetNotFound, got {other:?}"),
+        }
+
+        let locality = client
+            .find_locality("West End", Some("4101"))
+            .await
+            .expect("Failed to find locality by postcode");
+        assert_eq!(locality.id, 2);
+
+        let err = client
+            .find_locality("West End", Some("4000"))
+            .await
+            .expect_err("Postcode shouldn't match");
+        assert_eq!(
+            err.to_string(),
+            "Suburb West End isn't in postcode 4000, it's in 4101"
+        );
+
+        let err = client
+            .find_locality("Nowhere", Some("4101"))
+            .await
+            .expect_err("Suburb shouldn't exist");
+        match err {
+            BinError::LocalityNotFound { suggestions, .. } => assert_eq!(
+                suggestions,
+                vec!["Highgate Hill".to_string(), "West End".to_string()]
+            ),
+            other => panic!("Expected LocalityNotFound, got {other:?}"),
+        }
+
+        let err = client
+            .find_locality("West Edn", None)
+            .await
+            .expect_err("Suburb shouldn't exist");
+        assert_eq!(
+            err.to_string(),
+            "Suburb West Edn not found, did you mean West End?"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cached_localities() {
+        let server = MockServer::start().await;
+        let body = json!({
+            "localities": [
+                {"id": 2, "name": "West End", "postcode": "4101", "council": "Brisbane"},
+            ]
+        });
+        Mock::given(method("GET"))
+            .and(path("/api/v1/localities.json"))
+            .and(wiremock::matchers::header("If-None-Match", "\"v1\""))
+            .respond_with(ResponseTemplate::new(304))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/localities.json"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("ETag", "\"v1\"")
+                    .set_body_json(body),
+            )
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let cache = cache::tests::temp_cache("localities");
+        let client = |clock: FixedClock, cache: Cache| {
+            stub_client(&server).with_clock(clock).with_cache(cache)
+        };
+
+        // the first request fills the cache, then it's used until it expires
+        let mut fresh = client(brisbane_april_fools(), cache.clone());
+        for _ in 0..2 {
+            let localities = fresh
+                .get_localities()
+                .await
+                .expect("Failed to get localities");
+            assert_eq!(localities.len(), 1);
+        }
+
+        // a month later it's revalidated, and the server says it hasn't changed
+        let mut stale = client(clock_at(2024, time::Month::May, 1, 0), cache.clone());
+        let localities = stale.get_localities().await.expect("Failed to revalidate");
+        assert_eq!(localities[0].name, "West End");
+
+        // --refresh skips the freshness check, and without the ETag it's a full request
+        cache.clear().expect("Failed to clear cache");
+        let mut refresh = client(brisbane_april_fools(), cache.clone().with_refresh(true));
+        refresh.get_localities().await.expect("Failed to refresh");
+
+        server.verify().await;
+        let _ = std::fs::remove_dir_all(cache.dir());
+    }
+
+    #[tokio::test]
+    async fn test_offline_schedule() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/properties/2695626.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+                fixtures::bin_data_json(),
+                fixtures::recycling_json("2024-01-09"),
+            ])))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/properties/2695626.json"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let cache = cache::tests::temp_cache("schedules");
+        let client = |day: u8, offline: OfflineMode| {
+            stub_client(&server)
+                .with_clock(clock_at(2024, time::Month::January, day, 0))
+                .with_cache(cache.clone())
+                .with_offline(offline)
+                .with_retry(RetryPolicy::none())
+        };
+        let start = Date::from_calendar_date(2024, time::Month::January, 1).expect("valid date");
+        let end = Date::from_calendar_date(2024, time::Month::January, 31).expect("valid date");
+
+        // a successful response is kept
+        let schedule = client(5, OfflineMode::Fallback)
+            .get_property_schedule(2695626, start, end)
+            .await
+            .expect("Failed to get bin data");
+        assert!(!schedule.stale);
+        assert_eq!(schedule.bin_days.len(), 1);
+
+        // the server is down, so without the fallback it's an error
+        let err = client(8, OfflineMode::Online)
+            .get_property_schedule(2695626, start, end)
+            .await
+            .expect_err("The server should be down");
+        assert!(err.is_unavailable());
+
+        // with the fallback, the last known data is used and marked as stale
+        let mut fallback = client(8, OfflineMode::Fallback);
+        let schedule = fallback
+            .get_property_schedule(2695626, start, end)
+            .await
+            .expect("Failed to fall back to the cache");
+        assert!(schedule.stale);
+        assert_eq!(schedule.bin_data.property.id, Some(2695626));
+        assert_eq!(schedule.age(fallback.clock().now()), "3 days ago");
+
+        // offline doesn't ask the server at all, and only returns the days asked for
+        let later = Date::from_calendar_date(2024, time::Month::January, 10).expect("valid date");
+        let schedule = client(8, OfflineMode::Offline)
+            .get_property_schedule(2695626, later, end)
+            .await
+            .expect("Failed to use the cache offline");
+        assert!(schedule.stale);
+        assert!(schedule.bin_days.is_empty());
+
+        // but not dates that were never fetched
+        let february =
+            Date::from_calendar_date(2024, time::Month::February, 1).expect("valid date");
+        let err = client(8, OfflineMode::Offline)
+            .get_property_schedule(2695626, start, february)
+            .await
+            .expect_err("February isn't cached");
+        assert!(matches!(err, BinError::NotCached(_)));
+
+        // offline without a cache never asks the server either
+        let err = stub_client(&server)
+            .with_offline(OfflineMode::Offline)
+            .get_property_schedule(2695626, start, end)
+            .await
+            .expect_err("Nothing is cached without a cache");
+        assert!(matches!(err, BinError::NotCached(_)));
+
+        let err = client(8, OfflineMode::Offline)
+            .get_property_schedule(1, start, end)
+            .await
+            .expect_err("Nothing should be cached");
+        assert!(matches!(err, BinError::NotCached(_)));
+        assert_eq!(
+            err.to_string(),
+            "No cached bin data for property 1 from 2024-01-01 to 2024-01-31 to use offline"
+        );
+
+        server.verify().await;
+        let _ = std::fs::remove_dir_all(cache.dir());
+    }
+
+    #[tokio::test]
+    async fn test_timeout() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/localities.json"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({"localities": []}))
+                    .set_delay(std::time::Duration::from_secs(2)),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut client = stub_client(&server).with_retry(RetryPolicy {
+            timeout: std::time::Duration::from_millis(100),
+            ..RetryPolicy::none()
+        });
+        let err = client
+            .get_localities()
+            .await
+            .expect_err("The request should time out");
+        assert!(err.is_unavailable());
+    }
+
+    #[tokio::test]
+    async fn test_retries() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/localities.json"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/localities.json"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "0"))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/localities.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "localities": [
+                    {"id": 2, "name": "West End", "postcode": "4101", "council": "Brisbane"},
+                ]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/streets.json"))
+            .respond_with(ResponseTemplate::new(502))
+            .expect(3)
+            .mount(&server)
+            .await;
+
+        let mut client = stub_client(&server).with_retry(RetryPolicy {
+            max_attempts: 3,
+            base_delay: std::time::Duration::from_millis(1),
+            max_delay: std::time::Duration::from_secs(1),
+            jitter: false,
+            ..RetryPolicy::default()
+        });
+        // a 503, then a 429 that says to retry straight away, then success
+        let localities = client
+            .get_localities()
+            .await
+            .expect("Failed to get localities after retrying");
+        assert_eq!(localities.len(), 1);
+
+        let err = client
+            .get_streets(&2)
+            .await
+            .expect_err("Streets should keep failing");
+        let BinError::RetriesExhausted { attempts, last } = &err else {
+            panic!("Expected RetriesExhausted, got {err:?}");
+        };
+        assert_eq!(attempts.len(), 3);
+        assert_eq!(attempts[0].outcome, "HTTP status 502");
+        assert_eq!(attempts[2].delay, None);
+        assert!(matches!(**last, BinError::HttpStatus { status: 502, .. }));
+        assert!(err.is_unavailable());
+        assert!(err.to_string().contains(
+            "(gave up after: HTTP status 502, retried after 1.0ms; HTTP status 502, retried after 2.0ms; HTTP status 502)"
+        ));
+
+        server.verify().await;
+    }
+
+    #[tokio::test]
+    async fn test_user_agent_and_shared_limiter() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/localities.json"))
+            .and(wiremock::matchers::header("User-Agent", DEFAULT_USER_AGENT))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"localities": []})))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/localities.json"))
+            .and(wiremock::matchers::header("User-Agent", "bin-bot/2.0"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"localities": []})))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut client = stub_client(&server).with_rate_limit(RateLimit {
+            requests_per_second: 100.0,
+            burst: 1,
+            max_concurrency: 1,
+        });
+        assert!(DEFAULT_USER_AGENT.starts_with("brisbane-bin-data/"));
+        client
+            .get_localities()
+            .await
+            .expect("Failed to get localities");
+
+        // clones share the limiter, and the User-Agent can be replaced
+        let mut clone = client.clone().with_user_agent("bin-bot/2.0");
+        assert!(std::ptr::eq(client.rate_limiter(), clone.rate_limiter()));
+        assert_eq!(clone.rate_limiter().limit().max_concurrency, 1);
+        clone
+            .get_localities()
+            .await
+            .expect("Failed to get localities");
+
+        server.verify().await;
+    }
+
+    #[test]
+    fn test_property_schedule_age() {
+        let fetched = clock_at(2024, time::Month::January, 5, 0).now();
+        let schedule = |seconds: i64| PropertySchedule {
+            bin_data: fixtures::bin_data(),
+            bin_days: Vec::new(),
+            fetched: fetched - Duration::seconds(seconds),
+            stale: true,
+        };
+        assert_eq!(schedule(30).age(fetched), "just now");
+        assert_eq!(schedule(60).age(fetched), "1 minute ago");
+        assert_eq!(schedule(5 * 3600 + 10).age(fetched), "5 hours ago");
+        assert_eq!(schedule(86400).age(fetched), "1 day ago");
+        // a clock that went backwards isn't in the future
+        assert_eq!(schedule(-60).age(fetched), "just now");
+    }
+
+    #[test]
+    fn test_address_units() {
+        let parse = |address: &str| {
+            AddressData::try_from(address.to_string()).expect("Failed to parse address")
+        };
+
+        let address = parse("Unit 3, 12 Smith St, Paddington");
+        assert_eq!(address.unit.as_deref(), Some("3"));
+        assert_eq!(address.num, "12");
+        assert_eq!(address.street, "Smith St");
+        assert_eq!(address.address, "3/12 Smith St");
+        assert_eq!(address.suburb, "Paddington");
+
+        let address = parse("3/12-14 Smith St, Paddington");
+        assert_eq!(address.unit.as_deref(), Some("3"));
+        assert_eq!(address.num, "12-14");
+
+        let address = parse("Lot 5 Smith St, Paddington");
+        assert_eq!(address.lot.as_deref(), Some("5"));
+        assert_eq!(address.num, "");
+        assert_eq!(address.address, "Lot 5 Smith St");
+
+        let address = parse("12 Smith St, Paddington QLD 4064");
+        assert_eq!(address.suburb, "Paddington");
+        assert_eq!(address.state.as_deref(), Some("QLD"));
+        assert_eq!(address.postcode.as_deref(), Some("4064"));
+
+        let address = parse("12 Smith St, Paddington, QLD 4064");
+        assert_eq!(address.street, "Smith St");
+        assert_eq!(address.suburb, "Paddington");
+        assert_eq!(address.postcode.as_deref(), Some("4064"));
+
+        assert!(AddressData::try_from("12 Smith St, QLD 4064".to_string()).is_err());
+        assert!(AddressData::try_from("Unit 3, Smith St, Paddington".to_string()).is_err());
+        assert!(AddressData::try_from("12, Paddington".to_string()).is_err());
+
+        let message = |address: &str| match AddressData::try_from(address.to_string()) {
+            Err(BinError::InvalidAddress(message)) => message,
+            other => panic!("Expected InvalidAddress, got {other:?}"),
+        };
+        assert!(message(", West End").starts_with("No street provided"));
+        assert!(message("West End").starts_with("No street provided"));
+        assert!(message("123 drury lane").starts_with("No suburb provided"));
+    }
+
+    #[tokio::test]
+    async fn test_stub_house_numbers() {
+        let server = stub_server().await;
+        let mut client = stub_client(&server);
+        let find = |address: &str| {
+            AddressData::try_from(address.to_string()).expect("Failed to parse address")
+        };
+
+        // "2" is in "2-4" too, but the exact match wins
+        let property = client
+            .find_property(find("2 Boundary St, West End"))
+            .await
+            .expect("Failed to find property");
+        assert_eq!(property.id, 2695626);
+
+        let property = client
+            .find_property(find("4 Boundary St, West End"))
+            .await
+            .expect("Failed to find range");
+        assert_eq!(property.id, 2695624);
+
+        let property = client
+            .find_property(find("2/22 Boundary St, West End"))
+            .await
+            .expect("Failed to find unit");
+        assert_eq!(property.id, 2695702);
+
+        let err = client
+            .find_property(find("22 Boundary St, West End"))
+            .await
+            .expect_err("Should be ambiguous");
+        match err {
+            BinError::AmbiguousAddress { candidates, .. } => assert_eq!(
+                candidates.into_iter().map(|p| p.name).collect::<Vec<_>>(),
+                vec![
+                    "1/22 Boundary St".to_string(),
+                    "2/22 Boundary St".to_string()
+                ]
+            ),
+            other => panic!("Expected AmbiguousAddress, got {other:?}"),
+        }
+
+        let property = client
+            .find_property(find("Unit 1, 22 Boundary Street, West End"))
+            .await
+            .expect("Failed to find unit with a prefix");
+        assert_eq!(property.id, 2695701);
+
+        let property = client
+            .find_property(find("Lot 5 Boundary St, West End"))
+            .await
+            .expect("Failed to find lot");
+        assert_eq!(property.id, 2695805);
+
+        let err = client
+            .find_property(find("2A Boundary St, West End"))
+            .await
+            .expect_err("Shouldn't match 2");
+        assert!(matches!(err, BinError::PropertyNotFound(_)));
+    }
 
     #[tokio::test]
     async fn test_invalid_property() {
@@ -617,5 +2225,16 @@ mod tests {
                 assert!(collection_day.is_err());
             }
         }
+
+        // days are written as the API's numbers, and names from older caches are still read
+        let day: CollectionDay = serde_json::from_value(json!(2)).expect("Failed to parse number");
+        assert_eq!(
+            serde_json::to_value(&day).expect("Failed to serialize"),
+            json!(2)
+        );
+        let day: CollectionDay =
+            serde_json::from_value(json!("Tuesday")).expect("Failed to parse name");
+        assert_eq!(day, CollectionDay::Tuesday);
+        assert!(serde_json::from_value::<CollectionDay>(json!("Someday")).is_err());
     }
 }
@@ -15,37 +15,216 @@
 #![deny(clippy::unreachable)]
 #![deny(clippy::unwrap_used)]
 
-use brisbane_bin_data::{cli::Cli, AddressData};
+use brisbane_bin_data::{
+    cache::Cache,
+    calendar::{calendar_range, CalendarRenderer},
+    cli::{CacheAction, Cli, Command, ListCommand},
+    delimited::Listing,
+    ics::IcsOptions,
+    month_window,
+    prompt::find_property_interactive,
+    schedule::next_collection,
+    terminal::ColorDepth,
+    AddressData, NextCollection, OutputFormat, PropertySchedule, RenderOptions, Renderer,
+    ScheduleView,
+};
 use clap::Parser;
-use serde_json::json;
-use std::collections::HashSet;
+use std::{io::IsTerminal, process::ExitCode};
+use time::format_description;
 
-use brisbane_bin_data::{BinClient, BinDay};
+use brisbane_bin_data::{BinClient, BinError};
+
+/// Map an error to a process exit code, so scripts can tell "API down" from "bad address"
+fn exit_code(err: &BinError) -> ExitCode {
+    match err {
+        // the server being down is the same exit code with or without retries
+        _ if err.is_unavailable() => ExitCode::from(4),
+        BinError::RetriesExhausted { last, .. } => exit_code(last),
+        BinError::InvalidAddress(_)
+        | BinError::UnknownCouncil(_)
+        | BinError::InvalidDateRange { .. }
+        | BinError::AmbiguousAddress { .. }
+        | BinError::PostcodeMismatch { .. } => ExitCode::from(2),
+        BinError::LocalityNotFound { .. }
+        | BinError::StreetNotFound { .. }
+        | BinError::PropertyNotFound(_) => ExitCode::from(3),
+        BinError::Transport { .. } | BinError::HttpStatus { .. } | BinError::NotCached(_) => {
+            ExitCode::from(4)
+        }
+        // colours only come from the API, so a bad one is a response we didn't understand
+        BinError::Api(_)
+        | BinError::Json { .. }
+        | BinError::InvalidResponse(_)
+        | BinError::InvalidColour(_) => ExitCode::from(5),
+        BinError::InvalidUrl { .. } | BinError::Date { .. } => ExitCode::FAILURE,
+    }
+}
+
+/// Print an error and its sources to stderr, returning the matching exit code
+fn report(err: &BinError, debug: bool) -> ExitCode {
+    eprintln!("{err}");
+    if debug {
+        let mut source = std::error::Error::source(err);
+        while let Some(inner) = source {
+            eprintln!("  caused by: {inner}");
+            source = inner.source();
+        }
+    }
+    exit_code(err)
+}
+
+/// Tell the user when the bin data came from the cache, and how old it is
+fn report_staleness(client: &BinClient, schedule: &PropertySchedule) {
+    if !schedule.stale {
+        return;
+    }
+    let age = schedule.age(client.clock().now());
+    let fetched = format_description::parse_borrowed::<2>("[year]-[month]-[day] [hour]:[minute]")
+        .ok()
+        .and_then(|formatter| {
+            schedule
+                .fetched
+                .to_offset(client.council().timezone)
+                .format(&formatter)
+                .ok()
+        });
+    match fetched {
+        Some(fetched) => eprintln!("(cached {age}, fetched {fetched})"),
+        None => eprintln!("(cached {age})"),
+    }
+}
+
+/// Print the next collection, failing if there isn't one
+fn print_next(next: Option<NextCollection>) -> ExitCode {
+    match next {
+        Some(next) => {
+            println!("{next}");
+            ExitCode::SUCCESS
+        }
+        None => {
+            eprintln!("No upcoming collections found");
+            // nothing was found, like a missing property
+            ExitCode::from(3)
+        }
+    }
+}
+
+/// Write a listing, dropping anything that doesn't match the filter
+fn write_listing<T: Listing>(
+    format: OutputFormat,
+    mut items: Vec<T>,
+    filter: Option<&str>,
+) -> ExitCode {
+    if let Some(filter) = filter {
+        items.retain(|item| item.matches(filter));
+    }
+    if let Err(e) = format.write_listing(&mut std::io::stdout().lock(), &items) {
+        eprintln!("Failed to write output: {e}");
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+/// Run one of the listing commands, see [Command::listing]
+async fn list(client: &mut BinClient, command: ListCommand<'_>, cli: &Cli) -> ExitCode {
+    let format = cli.output_format();
+    let result = match command {
+        ListCommand::Localities { filter } => client
+            .get_council_localities()
+            .await
+            .map(|items| write_listing(format, items, filter)),
+        ListCommand::Streets { suburb, filter } => match client.find_locality(suburb, None).await {
+            Ok(locality) => client
+                .get_streets(&locality.id)
+                .await
+                .map(|items| write_listing(format, items, filter)),
+            Err(e) => Err(e),
+        },
+        ListCommand::Properties {
+            street,
+            suburb,
+            filter,
+        } => match client.find_street(street, suburb, None).await {
+            Ok(street) => client
+                .get_properties(&street.id)
+                .await
+                .map(|items| write_listing(format, items, filter)),
+            Err(e) => Err(e),
+        },
+    };
+    match result {
+        Ok(code) => code,
+        Err(e) => report(&e, cli.debug),
+    }
+}
 
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
     let cli = Cli::parse();
 
-    let mut client = BinClient::default();
+    let cache = Cache::from_env(|name| std::env::var(name).ok());
+    if let Some(Command::Cache {
+        action: CacheAction::Clear,
+    }) = &cli.command
+    {
+        let Some(cache) = cache else {
+            eprintln!("Couldn't find the cache directory, set XDG_CACHE_HOME or HOME");
+            return ExitCode::FAILURE;
+        };
+        return match cache.clear() {
+            Ok(removed) => {
+                println!(
+                    "Removed {removed} cached responses from {}",
+                    cache.dir().display()
+                );
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("Failed to clear {}: {e}", cache.dir().display());
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    let council = match cli.council() {
+        Ok(val) => val,
+        Err(e) => return report(&e, cli.debug),
+    };
+    let mut client = BinClient::default()
+        .with_council(council)
+        .with_retry(cli.retry_policy())
+        .with_rate_limit(cli.rate_limit())
+        // without a cache dir, --offline fails with nothing cached rather than going online
+        .with_offline(cli.offline_mode());
+    if let Some(cache) = cache.filter(|_| !cli.no_cache) {
+        client = client.with_cache(cache.with_refresh(cli.refresh));
+    }
     if cli.debug {
         client = client.with_debug();
     }
-
-    let bin_data = if let Some(property_id) = cli.property_id {
-        match client.get_property(property_id).await {
+    if let Some(base_url) = &cli.base_url {
+        client = match client.with_base_url(base_url) {
             Ok(val) => val,
-            Err(e) => {
-                eprintln!("{e}");
-                return;
-            }
-        }
+            Err(e) => return report(&e, cli.debug),
+        };
+    }
+
+    if let Some(command) = cli.command.as_ref().and_then(Command::listing) {
+        return list(&mut client, command, &cli).await;
+    }
+
+    let today = client.today();
+    let date_range = match cli.get_date_range(today) {
+        Ok(val) => val,
+        Err(e) => return report(&e, cli.debug),
+    };
+
+    let property_id = if let Some(property_id) = cli.property_id {
+        property_id
     } else {
         let address: AddressData = match cli.get_data() {
             Ok(val) => val,
-            Err(e) => {
-                eprintln!("{e}");
-                return;
-            }
+            Err(e) => return report(&e, cli.debug),
         };
 
         if cli.debug {
@@ -55,70 +234,153 @@ async fn main() {
             );
             eprintln!("Getting localities...");
         }
-        match client.get_address(address).await {
-            Ok(val) => val,
-            Err(e) => {
-                eprintln!("{e}");
-                return;
+        if !cli.no_input && std::io::stdin().is_terminal() {
+            let found = find_property_interactive(
+                &mut client,
+                address,
+                &mut std::io::stdin().lock(),
+                &mut std::io::stderr(),
+            )
+            .await;
+            match found {
+                Ok((property, picked)) => {
+                    if picked {
+                        eprintln!(
+                            "Using {} (property ID {}), pass -p {} to skip the lookup next time",
+                            property.name, property.id, property.id
+                        );
+                    }
+                    property.id
+                }
+                Err(e) => return report(&e, cli.debug),
+            }
+        } else {
+            match client.find_property(address).await {
+                Ok(val) => val.id,
+                Err(e) => return report(&e, cli.debug),
             }
         }
     };
 
-    let (bindata, bin_days) = (bin_data.0.clone(), bin_data.1.clone());
-    let mut days = HashSet::new();
-
-    bindata.dow.iter().for_each(|d| {
-        days.insert(d);
-    });
-    bindata.days_of_week.iter().for_each(|d| {
-        days.insert(d);
-    });
+    if cli.command == Some(Command::Next) {
+        return match client.get_upcoming_schedule(property_id).await {
+            Ok(schedule) => {
+                report_staleness(&client, &schedule);
+                print_next(next_collection(
+                    &schedule.bin_data,
+                    &schedule.bin_days,
+                    today,
+                ))
+            }
+            Err(e) => report(&e, cli.debug),
+        };
+    }
 
-    if cli.show_day {
-        for day in days {
-            println!("{day}");
+    let calendar_window = match cli.command {
+        Some(Command::Calendar { months }) => {
+            let start = date_range.map_or(today, |(start, _)| start);
+            match calendar_range(start, months) {
+                Ok(range) => Some((range, months)),
+                Err(e) => return report(&e, cli.debug),
+            }
         }
-    } else if cli.pretty {
-        println!("{}", bindata.property);
-
-        let mut bin_days_sorted = bin_days
-            .iter()
-            .filter(|day| {
-                if cli.future {
-                    match day.get_start_date() {
-                        Ok(val) => {
-                            if val < time::OffsetDateTime::now_utc().date() {
-                                return false;
-                            }
-                        }
-                        Err(_) => return false,
-                    }
-                }
-                true
-            })
-            .collect::<Vec<&BinDay>>();
+        _ => None,
+    };
+    let fetch_range = calendar_window.map(|(range, _)| range).or(date_range);
 
-        bin_days_sorted.sort_by_key(|f| {
-            f.get_start_date()
-                .unwrap_or(time::OffsetDateTime::now_utc().date())
-        });
-        for day in bin_days_sorted {
-            if cli.future {
-                match day.get_start_date() {
-                    Ok(val) => {
-                        if val < time::OffsetDateTime::now_utc().date() {
-                            continue;
-                        }
-                    }
-                    Err(_) => continue,
-                }
-                if day.get_start_date().is_err() {
-                    continue;
-                }
-            }
-            println!("- {day}");
+    let schedule = match fetch_range.map_or_else(|| month_window(today), Ok) {
+        Ok((start, end)) => client.get_property_schedule(property_id, start, end).await,
+        Err(e) => Err(e),
+    };
+    let PropertySchedule {
+        bin_data, bin_days, ..
+    } = match schedule {
+        Ok(val) => {
+            report_staleness(&client, &val);
+            val
         }
+        Err(e) => return report(&e, cli.debug),
+    };
+
+    let color = ColorDepth::detect(cli.color, std::io::stdout().is_terminal(), |name| {
+        std::env::var(name).ok()
+    });
+
+    let renderer: Box<dyn Renderer> = if let Some(((start, _), months)) = calendar_window {
+        Box::new(CalendarRenderer {
+            start,
+            months,
+            color,
+        })
     } else {
-        println!("{}", json!(&bin_data));
+        let mut ics_options = IcsOptions::new(client.clock().now());
+        ics_options.alarm = cli.alarm;
+        ics_options.range = match date_range {
+            Some(range) => Some(range),
+            None => month_window(today).ok(),
+        };
+        cli.output_format().renderer(RenderOptions {
+            ics: ics_options,
+            color,
+        })
+    };
+    let view = ScheduleView::new(
+        &bin_data,
+        &bin_days,
+        today,
+        cli.future && calendar_window.is_none(),
+    );
+    if let Err(e) = renderer.render(&view, &mut std::io::stdout().lock()) {
+        eprintln!("Failed to write output: {e}");
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use brisbane_bin_data::{rate_limit::RateLimit, retry::RetryPolicy};
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_exit_code_server_down() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/localities.json"))
+            .respond_with(
+                ResponseTemplate::new(503).set_body_json(
+                    serde_json::json!({"error": "Down for maintenance", "status": 503}),
+                ),
+            )
+            .mount(&server)
+            .await;
+
+        let quick = RetryPolicy {
+            base_delay: std::time::Duration::from_millis(1),
+            jitter: false,
+            ..RetryPolicy::default()
+        };
+        for retry in [RetryPolicy::none(), quick] {
+            let mut client = BinClient::default()
+                .with_base_url(&format!("{}/api/v1/", server.uri()))
+                .expect("Failed to set base URL")
+                .with_retry(retry)
+                .with_rate_limit(RateLimit {
+                    requests_per_second: 0.0,
+                    ..RateLimit::default()
+                });
+            let err = client
+                .get_localities()
+                .await
+                .expect_err("The server should be down");
+            assert_eq!(
+                exit_code(&err),
+                ExitCode::from(4),
+                "{err} with {} attempts",
+                retry.max_attempts
+            );
+        }
     }
 }
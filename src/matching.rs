@@ -0,0 +1,147 @@
+//! Forgiving name matching, so "2 Boundary Street" finds the API's "2 Boundary St"
+
+/// How many suggestions to put in a not-found error
+pub const MAX_SUGGESTIONS: usize = 3;
+
+/// Street types and their abbreviations, as used by Australia Post. Both are normalised to the abbreviation.
+const STREET_TYPES: &[(&str, &str)] = &[
+    ("alley", "ally"),
+    ("arcade", "arc"),
+    ("avenue", "ave"),
+    ("boulevard", "bvd"),
+    ("circuit", "cct"),
+    ("close", "cl"),
+    ("court", "ct"),
+    ("crescent", "cres"),
+    ("drive", "dr"),
+    ("esplanade", "esp"),
+    ("grove", "gr"),
+    ("highway", "hwy"),
+    ("lane", "ln"),
+    ("parade", "pde"),
+    ("place", "pl"),
+    ("road", "rd"),
+    ("square", "sq"),
+    ("street", "st"),
+    ("terrace", "tce"),
+];
+
+/// Lowercase a name, turn punctuation into spaces, and abbreviate street types, so
+/// "Boundary Street" and "boundary st." compare equal
+pub fn normalise(name: &str) -> String {
+    name.to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .map(|word| {
+            STREET_TYPES
+                .iter()
+                .find(|(full, short)| word == *full || word == *short)
+                .map_or(word, |(_, short)| short)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The Levenshtein distance between two strings, counted in characters
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// A candidate for a search, and how far its name is from what was asked for
+pub struct Ranked<'a, T> {
+    /// The candidate
+    pub item: &'a T,
+    /// Where the candidate is in the items that were ranked, to take it out of them
+    pub index: usize,
+    /// The edit distance between the normalised names, 0 is a match
+    pub distance: usize,
+}
+
+/// Rank the items whose names are close to `query`, best first. Close means within a few typos, or
+/// containing (or contained in) the query, and an exact match after normalising has distance 0.
+pub fn rank<'a, T>(query: &str, items: &'a [T], name: impl Fn(&T) -> &str) -> Vec<Ranked<'a, T>> {
+    let query = normalise(query);
+    let max_distance = (query.chars().count() / 3).max(2);
+    let mut ranked = items
+        .iter()
+        .enumerate()
+        .filter_map(|(index, item)| {
+            let candidate = normalise(name(item));
+            let distance = edit_distance(&query, &candidate);
+            let related =
+                !query.is_empty() && (candidate.contains(&query) || query.contains(&candidate));
+            (distance <= max_distance || related).then_some(Ranked {
+                item,
+                index,
+                distance,
+            })
+        })
+        .collect::<Vec<_>>();
+    ranked.sort_by_key(|ranked| ranked.distance);
+    ranked
+}
+
+/// The names of the best few candidates, for a not-found error
+pub fn suggestions<T>(ranked: &[Ranked<'_, T>], name: impl Fn(&T) -> &str) -> Vec<String> {
+    ranked
+        .iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|ranked| name(ranked.item).to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalise() {
+        assert_eq!(normalise("Boundary Street"), "boundary st");
+        assert_eq!(normalise("  boundary   st. "), "boundary st");
+        assert_eq!(normalise("O'Connell Tce"), "o connell tce");
+        assert_eq!(normalise("Montague Road"), normalise("MONTAGUE RD"));
+        // only whole words are abbreviated
+        assert_eq!(normalise("Streeton Pde"), "streeton pde");
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("west end", "west end"), 0);
+        assert_eq!(edit_distance("west edn", "west end"), 2);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn test_rank() {
+        let suburbs = ["Highgate Hill", "West End", "Westlake", "Woolloongabba"];
+        let ranked = rank("west edn", &suburbs, |s| s);
+        assert_eq!(ranked.first().map(|r| *r.item), Some("West End"));
+        assert_eq!(ranked.first().map(|r| r.index), Some(1));
+        assert!(!ranked.iter().any(|r| *r.item == "Woolloongabba"));
+
+        let ranked = rank("WEST END", &suburbs, |s| s);
+        assert_eq!(ranked.first().map(|r| r.distance), Some(0));
+
+        let ranked = rank("west", &suburbs, |s| s);
+        assert_eq!(
+            suggestions(&ranked, |s| s),
+            vec!["West End".to_string(), "Westlake".to_string()]
+        );
+        assert!(rank("Nowhere", &suburbs, |s| s).is_empty());
+    }
+}
@@ -0,0 +1,229 @@
+//! Asking the user to pick when an address lookup is ambiguous or doesn't find anything
+
+use std::io::{BufRead, Write};
+
+use crate::{AddressData, BinClient, BinError, Property};
+
+/// Ask the user to pick one of `options` by number. Returns `None` if they give up by entering
+/// nothing, or the input ends.
+pub fn choose(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    heading: &str,
+    options: &[String],
+) -> std::io::Result<Option<usize>> {
+    if options.is_empty() {
+        return Ok(None);
+    }
+    writeln!(output, "{heading}")?;
+    for (index, option) in options.iter().enumerate() {
+        writeln!(output, "{:>3}) {option}", index + 1)?;
+    }
+    loop {
+        write!(
+            output,
+            "Pick a number from 1 to {} (or press enter to give up): ",
+            options.len()
+        )?;
+        output.flush()?;
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            return Ok(None);
+        }
+        match line.trim().parse::<usize>() {
+            Ok(choice) if (1..=options.len()).contains(&choice) => return Ok(Some(choice - 1)),
+            _ => writeln!(output, "{:?} isn't one of the choices", line.trim())?,
+        }
+    }
+}
+
+/// Look up the property for an address like [BinClient::find_property], but when the suburb or street
+/// isn't found, or more than one property matches, ask the user to pick from the candidates. When
+/// nothing is close enough to suggest, they pick from all the suburbs, streets or properties.
+///
+/// Returns the property, and whether the user had to pick it. If they give up, or there's nothing to
+/// pick from, the original error is returned.
+pub async fn find_property_interactive(
+    client: &mut BinClient,
+    mut address: AddressData,
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> Result<(Property, bool), BinError> {
+    let mut picked = false;
+    loop {
+        let err = match client.find_property(address.clone()).await {
+            Ok(property) => return Ok((property, picked)),
+            Err(err) => err,
+        };
+        // giving up and failing to ask both mean we return the original error
+        match &err {
+            BinError::LocalityNotFound { name, suggestions } => {
+                // with nothing close to pick from, list every suburb
+                let (heading, mut options) = if suggestions.is_empty() {
+                    let localities = client.get_council_localities().await?;
+                    (
+                        format!("Couldn't find suburb {name}, the suburbs are:"),
+                        localities.into_iter().map(|l| l.name).collect(),
+                    )
+                } else {
+                    (
+                        format!("Couldn't find suburb {name}, did you mean:"),
+                        suggestions.clone(),
+                    )
+                };
+                let Some(index) = choose(input, output, &heading, &options).ok().flatten() else {
+                    return Err(err);
+                };
+                address.suburb = options.swap_remove(index);
+                address.postcode = None;
+            }
+            BinError::StreetNotFound { name, suggestions } => {
+                // with nothing close to pick from, list every street in the suburb
+                let (heading, mut options) = if suggestions.is_empty() {
+                    let locality = client
+                        .find_locality(&address.suburb, address.postcode.as_deref())
+                        .await?;
+                    let streets = client.get_streets(&locality.id).await?;
+                    (
+                        format!(
+                            "Couldn't find {name} in {}, the streets are:",
+                            locality.name
+                        ),
+                        streets.into_iter().map(|s| s.name).collect(),
+                    )
+                } else {
+                    (
+                        format!("Couldn't find {name} in {}, did you mean:", address.suburb),
+                        suggestions.clone(),
+                    )
+                };
+                let Some(index) = choose(input, output, &heading, &options).ok().flatten() else {
+                    return Err(err);
+                };
+                address.street = options.swap_remove(index);
+            }
+            BinError::AmbiguousAddress { candidates, .. } => {
+                let names = candidates
+                    .iter()
+                    .map(|p| p.name.clone())
+                    .collect::<Vec<_>>();
+                let heading = format!("More than one property matches {}:", address.address);
+                let Some(index) = choose(input, output, &heading, &names).ok().flatten() else {
+                    return Err(err);
+                };
+                return Ok((candidates[index].clone(), true));
+            }
+            BinError::PropertyNotFound(_) => {
+                let street = client
+                    .find_street(
+                        &address.street,
+                        &address.suburb,
+                        address.postcode.as_deref(),
+                    )
+                    .await?;
+                let mut properties = client.get_properties(&street.id).await?;
+                let names = properties
+                    .iter()
+                    .map(|p| p.name.clone())
+                    .collect::<Vec<_>>();
+                let heading = format!(
+                    "Couldn't find {} on {}, the properties are:",
+                    address.address, street.name
+                );
+                let Some(index) = choose(input, output, &heading, &names).ok().flatten() else {
+                    return Err(err);
+                };
+                return Ok((properties.swap_remove(index), true));
+            }
+            _ => return Err(err),
+        }
+        picked = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{stub_client, stub_server};
+
+    async fn pick(address: &str, answers: &str) -> Result<(Property, bool), BinError> {
+        let server = stub_server().await;
+        let mut client = stub_client(&server);
+        let address = AddressData::try_from(address.to_string()).expect("Failed to parse address");
+        let mut output = Vec::new();
+        find_property_interactive(&mut client, address, &mut answers.as_bytes(), &mut output).await
+    }
+
+    #[tokio::test]
+    async fn test_find_property_interactive() {
+        let (property, picked) = pick("2 Boundary St, West End", "")
+            .await
+            .expect("Failed to find property");
+        assert_eq!(property.id, 2695626);
+        assert!(!picked);
+
+        let (property, picked) = pick("2 Boundry St, West Edn", "1\n1\n")
+            .await
+            .expect("Failed to pick suburb and street");
+        assert_eq!(property.id, 2695626);
+        assert!(picked);
+
+        // nothing is close enough to suggest, so all the suburbs and streets are listed
+        let (property, picked) = pick("2 Boundary St, Zzyzx", "2\n")
+            .await
+            .expect("Failed to pick from all the suburbs");
+        assert_eq!(property.id, 2695626);
+        assert!(picked);
+        let (property, _) = pick("2 Wharf Rd, West End", "1\n")
+            .await
+            .expect("Failed to pick from all the streets");
+        assert_eq!(property.id, 2695626);
+
+        let (property, _) = pick("22 Boundary St, West End", "2\n")
+            .await
+            .expect("Failed to pick unit");
+        assert_eq!(property.id, 2695702);
+
+        let (property, _) = pick("99 Boundary St, West End", "2\n")
+            .await
+            .expect("Failed to pick from the street");
+        assert_eq!(property.id, 2695626);
+
+        let err = pick("22 Boundary St, West End", "\n")
+            .await
+            .expect_err("Giving up should return the error");
+        assert!(matches!(err, BinError::AmbiguousAddress { .. }));
+    }
+
+    #[test]
+    fn test_choose() {
+        let options = vec!["West End".to_string(), "Westlake".to_string()];
+
+        let mut output = Vec::new();
+        let choice = choose(
+            &mut "3\nwest\n2\n".as_bytes(),
+            &mut output,
+            "Did you mean:",
+            &options,
+        )
+        .expect("Failed to choose");
+        assert_eq!(choice, Some(1));
+        let output = String::from_utf8(output).expect("valid utf8");
+        assert!(output.starts_with("Did you mean:\n  1) West End\n  2) Westlake\n"));
+        assert!(output.contains("\"3\" isn't one of the choices"));
+
+        let mut output = Vec::new();
+        assert_eq!(
+            choose(&mut "\n".as_bytes(), &mut output, "Pick", &options).expect("Failed to choose"),
+            None
+        );
+        assert_eq!(
+            choose(&mut "".as_bytes(), &mut output, "Pick", &options).expect("Failed to choose"),
+            None
+        );
+        assert_eq!(
+            choose(&mut "1\n".as_bytes(), &mut output, "Pick", &[]).expect("Failed to choose"),
+            None
+        );
+    }
+}
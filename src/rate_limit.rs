@@ -0,0 +1,176 @@
+//! Keeping our requests polite when looking up lots of properties, with a token bucket for the request
+//! rate and a cap on how many are in flight at once
+
+use std::{
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// The User-Agent sent with every request, unless the client or council sets another
+pub const DEFAULT_USER_AGENT: &str = concat!(
+    env!("CARGO_PKG_NAME"),
+    "/",
+    env!("CARGO_PKG_VERSION"),
+    " (bin collection lookup)"
+);
+
+/// The longest a request waits for a token, however slow the rate
+const MAX_WAIT: Duration = Duration::from_secs(3600);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// How fast, and how many at once, requests may be sent
+pub struct RateLimit {
+    /// The average number of requests per second, zero or less means no limit
+    pub requests_per_second: f64,
+    /// How many requests can be sent straight away before the rate applies
+    pub burst: u32,
+    /// How many requests can be waiting for a response at once, at least 1
+    pub max_concurrency: usize,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        Self {
+            requests_per_second: 5.0,
+            burst: 5,
+            max_concurrency: 4,
+        }
+    }
+}
+
+#[derive(Debug)]
+/// The tokens available, and when they were last topped up
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    /// Take a token at `now`, returning how long to wait before it can be used. Tokens can go
+    /// negative, which reserves the next ones for whoever is already waiting.
+    fn take(&mut self, limit: &RateLimit, now: Instant) -> Duration {
+        if limit.requests_per_second <= 0.0 || !limit.requests_per_second.is_finite() {
+            return Duration::ZERO;
+        }
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens =
+            (self.tokens + elapsed * limit.requests_per_second).min(f64::from(limit.burst.max(1)));
+        self.updated = now;
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::try_from_secs_f64(-self.tokens / limit.requests_per_second)
+                .map_or(MAX_WAIT, |wait| wait.min(MAX_WAIT))
+        }
+    }
+}
+
+#[derive(Debug)]
+/// A token bucket and a concurrency cap, shared by every clone of a [crate::BinClient]
+pub struct RateLimiter {
+    limit: RateLimit,
+    bucket: Mutex<Bucket>,
+    permits: Semaphore,
+}
+
+impl RateLimiter {
+    /// A limiter with a full bucket
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            bucket: Mutex::new(Bucket {
+                tokens: f64::from(limit.burst.max(1)),
+                updated: Instant::now(),
+            }),
+            permits: Semaphore::new(limit.max_concurrency.max(1)),
+        }
+    }
+
+    /// The limits this limiter enforces
+    pub fn limit(&self) -> &RateLimit {
+        &self.limit
+    }
+
+    /// Wait for a free slot and a token, returning a permit to hold until the response has been read.
+    /// The semaphore is never closed, so this is only `None` if that changes.
+    pub async fn acquire(&self) -> Option<SemaphorePermit<'_>> {
+        let permit = self.permits.acquire().await.ok();
+        let wait = self
+            .bucket
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take(&self.limit, Instant::now());
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+        permit
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(RateLimit::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket() {
+        let limit = RateLimit {
+            requests_per_second: 2.0,
+            burst: 2,
+            max_concurrency: 1,
+        };
+        let start = Instant::now();
+        let mut bucket = Bucket {
+            tokens: 2.0,
+            updated: start,
+        };
+        // the burst goes straight out, then requests are spaced half a second apart
+        assert_eq!(bucket.take(&limit, start), Duration::ZERO);
+        assert_eq!(bucket.take(&limit, start), Duration::ZERO);
+        assert_eq!(bucket.take(&limit, start), Duration::from_millis(500));
+        assert_eq!(bucket.take(&limit, start), Duration::from_secs(1));
+
+        // after a long pause the bucket is full again, but no fuller
+        let later = start + Duration::from_secs(60);
+        assert_eq!(bucket.take(&limit, later), Duration::ZERO);
+        assert_eq!(bucket.take(&limit, later), Duration::ZERO);
+        assert_eq!(bucket.take(&limit, later), Duration::from_millis(500));
+
+        // a rate so slow the wait doesn't fit in a Duration waits as long as we allow
+        let glacial = RateLimit {
+            requests_per_second: 1e-20,
+            ..limit
+        };
+        assert_eq!(bucket.take(&glacial, later), MAX_WAIT);
+
+        let unlimited = RateLimit {
+            requests_per_second: 0.0,
+            ..limit
+        };
+        for _ in 0..10 {
+            assert_eq!(bucket.take(&unlimited, later), Duration::ZERO);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrency() {
+        let limiter = RateLimiter::new(RateLimit {
+            requests_per_second: 0.0,
+            burst: 1,
+            max_concurrency: 2,
+        });
+        let first = limiter.acquire().await.expect("Failed to acquire");
+        let _second = limiter.acquire().await.expect("Failed to acquire");
+        assert_eq!(limiter.permits.available_permits(), 0);
+        drop(first);
+        assert_eq!(limiter.permits.available_permits(), 1);
+    }
+}
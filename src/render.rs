@@ -0,0 +1,309 @@
+//! Rendering bin data for output, so every format shares the same filtering and sorting
+
+use std::io::Write;
+
+use clap::ValueEnum;
+use serde_json::json;
+use time::Date;
+
+use crate::{
+    delimited::{write_bin_days, write_listing, Delimiter, Listing},
+    ics::{write_ics, IcsOptions},
+    terminal::ColorDepth,
+    BinData, BinDay, CollectionDay,
+};
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// The ways bin data can be written out
+pub enum OutputFormat {
+    /// JSON, the property data and bin days as the API sent them
+    #[default]
+    Json,
+    /// A human-friendly list of the property details and bin days
+    Pretty,
+    /// Just the collection day(s)
+    Days,
+    /// Comma separated values, one row per bin day
+    Csv,
+    /// Tab separated values, one row per bin day
+    Tsv,
+    /// An iCalendar file, for importing into calendar apps
+    Ics,
+}
+
+#[derive(Debug, Clone)]
+/// Options for the renderers that need them
+pub struct RenderOptions {
+    /// Used by [OutputFormat::Ics]
+    pub ics: IcsOptions,
+    /// Used by [OutputFormat::Pretty]
+    pub color: ColorDepth,
+}
+
+impl OutputFormat {
+    /// Get the renderer for this format
+    pub fn renderer(self, options: RenderOptions) -> Box<dyn Renderer> {
+        match self {
+            OutputFormat::Json => Box::new(JsonRenderer),
+            OutputFormat::Pretty => Box::new(PrettyRenderer {
+                color: options.color,
+            }),
+            OutputFormat::Days => Box::new(DaysRenderer),
+            OutputFormat::Csv => Box::new(DelimitedRenderer(Delimiter::Comma)),
+            OutputFormat::Tsv => Box::new(DelimitedRenderer(Delimiter::Tab)),
+            OutputFormat::Ics => Box::new(IcsRenderer(options.ics)),
+        }
+    }
+}
+
+impl OutputFormat {
+    /// Write a locality, street or property listing. JSON writes an array, CSV and TSV write one row
+    /// per item, and everything else writes an aligned table.
+    pub fn write_listing<T: Listing>(
+        self,
+        mut writer: &mut dyn Write,
+        items: &[T],
+    ) -> std::io::Result<()> {
+        match self {
+            OutputFormat::Json => writeln!(writer, "{}", json!(items)),
+            OutputFormat::Csv => write_listing(&mut writer, Delimiter::Comma, items),
+            OutputFormat::Tsv => write_listing(&mut writer, Delimiter::Tab, items),
+            OutputFormat::Pretty | OutputFormat::Days | OutputFormat::Ics => {
+                let rows = items.iter().map(Listing::row).collect::<Vec<_>>();
+                let widths = T::HEADER
+                    .iter()
+                    .enumerate()
+                    .map(|(index, heading)| {
+                        rows.iter()
+                            .filter_map(|row| row.get(index))
+                            .map(|field| field.chars().count())
+                            .chain(std::iter::once(heading.len()))
+                            .max()
+                            .unwrap_or_default()
+                    })
+                    .collect::<Vec<_>>();
+                let header = T::HEADER.iter().map(|heading| heading.to_uppercase());
+                for row in std::iter::once(header.collect::<Vec<_>>()).chain(rows) {
+                    let line = row
+                        .iter()
+                        .zip(&widths)
+                        .map(|(field, width)| format!("{field:<width$}"))
+                        .collect::<Vec<_>>()
+                        .join("  ");
+                    writeln!(writer, "{}", line.trim_end())?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// The bin data for a property, with the bin days filtered and sorted ready for rendering
+pub struct ScheduleView<'a> {
+    /// The property data
+    pub bin_data: &'a BinData,
+    /// The bin days, in date order
+    pub bin_days: Vec<&'a BinDay>,
+    /// Every bin day in the order the API sent them, for formats that pass them through untouched
+    pub all_days: &'a [BinDay],
+    /// Today's date in the council's timezone
+    pub today: Date,
+}
+
+impl<'a> ScheduleView<'a> {
+    /// Sort the bin days by date, dropping any before `today` if `future_only` is set. JSON and iCalendar
+    /// output use [ScheduleView::all_days] instead, so they're unaffected.
+    pub fn new(
+        bin_data: &'a BinData,
+        bin_days: &'a [BinDay],
+        today: Date,
+        future_only: bool,
+    ) -> Self {
+        let mut sorted = bin_days
+            .iter()
+            .filter(|day| !future_only || day.start >= today)
+            .collect::<Vec<&BinDay>>();
+        sorted.sort_by_key(|day| day.start);
+        Self {
+            bin_data,
+            bin_days: sorted,
+            all_days: bin_days,
+            today,
+        }
+    }
+
+    /// The collection days for the property, without duplicates
+    pub fn collection_days(&self) -> Vec<&'a CollectionDay> {
+        let mut days = Vec::new();
+        for day in self
+            .bin_data
+            .dow
+            .iter()
+            .chain(self.bin_data.days_of_week.iter())
+        {
+            if !days.contains(&day) {
+                days.push(day);
+            }
+        }
+        days
+    }
+}
+
+/// Writes a [ScheduleView] in some format
+pub trait Renderer {
+    /// Write the view to `writer`
+    fn render(&self, view: &ScheduleView<'_>, writer: &mut dyn Write) -> std::io::Result<()>;
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Writes the property data and bin days as a JSON array, just as the API sent them
+pub struct JsonRenderer;
+
+impl Renderer for JsonRenderer {
+    fn render(&self, view: &ScheduleView<'_>, writer: &mut dyn Write) -> std::io::Result<()> {
+        writeln!(writer, "{}", json!([view.bin_data, view.all_days]))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+/// Writes the property details, then a list of bin days, each as a badge in the bin's colours
+pub struct PrettyRenderer {
+    /// How to colour the bin days
+    pub color: ColorDepth,
+}
+
+impl Renderer for PrettyRenderer {
+    fn render(&self, view: &ScheduleView<'_>, writer: &mut dyn Write) -> std::io::Result<()> {
+        writeln!(writer, "{}", view.bin_data.property)?;
+        for day in &view.bin_days {
+            if self.color == ColorDepth::None {
+                writeln!(writer, "- {day}")?;
+            } else {
+                let badge = self
+                    .color
+                    .badge(&format!(" {day} "), &day.color, &day.text_colour);
+                writeln!(writer, "- {badge}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Writes just the collection day(s), one per line
+pub struct DaysRenderer;
+
+impl Renderer for DaysRenderer {
+    fn render(&self, view: &ScheduleView<'_>, writer: &mut dyn Write) -> std::io::Result<()> {
+        for day in view.collection_days() {
+            writeln!(writer, "{day}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Writes one row per bin day, see [crate::delimited]
+pub struct DelimitedRenderer(pub Delimiter);
+
+impl Renderer for DelimitedRenderer {
+    fn render(&self, view: &ScheduleView<'_>, mut writer: &mut dyn Write) -> std::io::Result<()> {
+        write_bin_days(&mut writer, self.0, view.bin_data, &view.bin_days)
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Writes an iCalendar, see [crate::ics]
+pub struct IcsRenderer(pub IcsOptions);
+
+impl Renderer for IcsRenderer {
+    fn render(&self, view: &ScheduleView<'_>, mut writer: &mut dyn Write) -> std::io::Result<()> {
+        write_ics(&mut writer, view.bin_data, view.all_days, &self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fixtures, CollectionDay};
+
+    fn render(format: OutputFormat, view: &ScheduleView<'_>) -> String {
+        let mut buf = Vec::new();
+        format
+            .renderer(RenderOptions {
+                ics: IcsOptions::new(time::OffsetDateTime::UNIX_EPOCH),
+                color: ColorDepth::None,
+            })
+            .render(view, &mut buf)
+            .expect("Failed to render");
+        String::from_utf8(buf).expect("valid utf8")
+    }
+
+    #[test]
+    fn test_view_filters_and_sorts() {
+        let bin_data = fixtures::bin_data();
+        let bin_days = vec![
+            fixtures::recycling("2025-10-28"),
+            fixtures::recycling("2025-10-14"),
+        ];
+        let today = Date::from_calendar_date(2025, time::Month::October, 17).expect("valid date");
+
+        let view = ScheduleView::new(&bin_data, &bin_days, today, false);
+        assert_eq!(view.bin_days.len(), 2);
+        assert!(view.bin_days[0].start < view.bin_days[1].start);
+
+        let view = ScheduleView::new(&bin_data, &bin_days, today, true);
+        assert_eq!(view.bin_days.len(), 1);
+        assert_eq!(view.bin_days[0].start.to_string(), "2025-10-28");
+    }
+
+    #[test]
+    fn test_renderers() {
+        let mut bin_data = fixtures::bin_data();
+        bin_data.days_of_week.push(CollectionDay::Friday);
+        let bin_days = vec![
+            fixtures::recycling("2025-10-28"),
+            fixtures::recycling("2025-10-14"),
+        ];
+        let today = Date::from_calendar_date(2025, time::Month::October, 17).expect("valid date");
+        let view = ScheduleView::new(&bin_data, &bin_days, today, true);
+
+        assert_eq!(render(OutputFormat::Days, &view), "Tuesday\nFriday\n");
+        assert!(render(OutputFormat::Pretty, &view).ends_with("- 2025-10-28 Recycling recycle\n"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&render(OutputFormat::Json, &view)).expect("valid JSON");
+        // JSON passes the bin days through as they came, unsorted and unfiltered
+        assert_eq!(json[1].as_array().map(|days| days.len()), Some(2));
+        assert_eq!(json[1][0]["start"], "2025-10-28");
+
+        assert_eq!(render(OutputFormat::Csv, &view).lines().count(), 2);
+        assert!(render(OutputFormat::Ics, &view).starts_with("BEGIN:VCALENDAR"));
+    }
+
+    #[test]
+    fn test_listing() {
+        let streets: Vec<crate::Street> = serde_json::from_value(json!([
+            {"id": 1, "name": "Boundary St", "locality": "West End"},
+            {"id": 22, "name": "Vulture St", "locality": "West End"},
+        ]))
+        .expect("Failed to parse streets");
+
+        let mut buf = Vec::new();
+        OutputFormat::Pretty
+            .write_listing(&mut buf, &streets)
+            .expect("Failed to write listing");
+        assert_eq!(
+            String::from_utf8(buf).expect("valid utf8"),
+            "ID  NAME         LOCALITY\n1   Boundary St  West End\n22  Vulture St   West End\n"
+        );
+
+        let mut buf = Vec::new();
+        OutputFormat::Json
+            .write_listing(&mut buf, &streets)
+            .expect("Failed to write listing");
+        let json: serde_json::Value = serde_json::from_slice(&buf).expect("valid JSON");
+        assert_eq!(json[1]["name"], "Vulture St");
+    }
+}
@@ -0,0 +1,196 @@
+//! Retrying requests that failed for reasons that might go away, like timeouts and 5xx responses
+
+use std::{
+    collections::hash_map::RandomState,
+    fmt::Display,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+use reqwest::{
+    header::{HeaderMap, RETRY_AFTER},
+    StatusCode,
+};
+use time::{format_description::well_known::Rfc2822, OffsetDateTime};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// How often, and how patiently, to retry a request that failed with a connection error, a timeout,
+/// 429 Too Many Requests or a 5xx status
+pub struct RetryPolicy {
+    /// How many times to try a request, including the first, 1 means never retry
+    pub max_attempts: u32,
+    /// How long to wait before the first retry, doubling for each one after that
+    pub base_delay: Duration,
+    /// The longest to wait between attempts. If the server asks for a longer wait with
+    /// Retry-After, we give up instead.
+    pub max_delay: Duration,
+    /// Wait a random amount between half and all of each delay, so clients don't retry in lockstep
+    pub jitter: bool,
+    /// How long to wait for a connection to the server before the attempt fails
+    pub connect_timeout: Duration,
+    /// How long each attempt can take altogether, including reading the response
+    pub timeout: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: true,
+            connect_timeout: Duration::from_secs(10),
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Never retry, every failure is returned straight away
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// An HTTP client that gives up on attempts after this policy's timeouts. If the client can't be
+    /// built with them, e.g. because the TLS backend fails to load, this falls back to the default client.
+    pub fn http_client(&self) -> reqwest::Client {
+        reqwest::Client::builder()
+            .connect_timeout(self.connect_timeout)
+            .timeout(self.timeout)
+            .build()
+            .unwrap_or_default()
+    }
+
+    /// How long to wait after failed attempt number `attempt` (from 1), or `None` to give up.
+    /// `retry_after` is how long the server asked us to wait, if it did.
+    pub fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+        if let Some(retry_after) = retry_after {
+            return (retry_after <= self.max_delay).then_some(retry_after);
+        }
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_delay);
+        if !self.jitter {
+            return Some(backoff);
+        }
+        let half = backoff / 2;
+        let random = RandomState::new().build_hasher().finish();
+        let nanos = u64::try_from(half.as_nanos()).unwrap_or(u64::MAX);
+        Some(half + Duration::from_nanos(random.checked_rem(nanos).unwrap_or(0)))
+    }
+}
+
+/// Is this a status worth retrying, i.e. 429 Too Many Requests or a server error?
+pub fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Is this a transport error worth retrying, i.e. the connection failed or timed out?
+pub fn is_retryable_error(err: &reqwest::Error) -> bool {
+    err.is_connect() || err.is_timeout()
+}
+
+/// How long the Retry-After header asks us to wait, either in seconds or until a date
+pub fn retry_after(headers: &HeaderMap, now: OffsetDateTime) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let until = OffsetDateTime::parse(value, &Rfc2822).ok()?;
+    Some(Duration::try_from(until - now).unwrap_or(Duration::ZERO))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A failed attempt at a request, kept for the error when we give up
+pub struct Attempt {
+    /// What went wrong, e.g. "HTTP status 503"
+    pub outcome: String,
+    /// How long we waited before trying again, `None` for the last attempt
+    pub delay: Option<Duration>,
+}
+
+impl Display for Attempt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.delay {
+            Some(delay) => write!(f, "{}, retried after {delay:.1?}", self.outcome),
+            None => write!(f, "{}", self.outcome),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn test_delay() {
+        let policy = RetryPolicy {
+            max_attempts: 4,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(300),
+            jitter: false,
+            ..RetryPolicy::default()
+        };
+        assert_eq!(policy.delay(1, None), Some(Duration::from_millis(100)));
+        assert_eq!(policy.delay(2, None), Some(Duration::from_millis(200)));
+        // capped at the max delay
+        assert_eq!(policy.delay(3, None), Some(Duration::from_millis(300)));
+        assert_eq!(policy.delay(4, None), None);
+
+        assert_eq!(
+            policy.delay(1, Some(Duration::from_millis(250))),
+            Some(Duration::from_millis(250))
+        );
+        assert_eq!(policy.delay(1, Some(Duration::from_secs(60))), None);
+        assert_eq!(RetryPolicy::none().delay(1, None), None);
+
+        let jittered = RetryPolicy {
+            jitter: true,
+            ..policy
+        };
+        for _ in 0..20 {
+            let delay = jittered.delay(2, None).expect("Should retry");
+            assert!(delay >= Duration::from_millis(100) && delay <= Duration::from_millis(200));
+        }
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let now = OffsetDateTime::from_unix_timestamp(1_700_000_000).expect("valid timestamp");
+        let headers = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(
+                RETRY_AFTER,
+                HeaderValue::from_str(value).expect("valid header"),
+            );
+            headers
+        };
+        assert_eq!(
+            retry_after(&headers("120"), now),
+            Some(Duration::from_secs(120))
+        );
+        // 2023-11-14 22:13:20 UTC is the timestamp above
+        assert_eq!(
+            retry_after(&headers("Tue, 14 Nov 2023 22:13:50 GMT"), now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            retry_after(&headers("Tue, 14 Nov 2023 22:00:00 GMT"), now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(retry_after(&headers("soon"), now), None);
+        assert_eq!(retry_after(&HeaderMap::new(), now), None);
+
+        assert!(is_retryable_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable_status(StatusCode::BAD_GATEWAY));
+        assert!(!is_retryable_status(StatusCode::NOT_FOUND));
+    }
+}
@@ -0,0 +1,481 @@
+//! Working out when each bin goes out, and what goes out on the next bin night
+
+use std::fmt::Display;
+
+use serde::Serialize;
+use time::{format_description, Date, Duration, Weekday};
+
+use crate::{BinClient, BinData, BinDay, BinError, BinKind, PropertySchedule, Rgb};
+
+/// How far ahead to look for the next collection, long enough to see both weeks of a fortnightly cycle
+const LOOKAHEAD_DAYS: i64 = 14;
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+/// The next collection for a property, and which bins go out for it
+pub struct NextCollection {
+    /// The collection date, put the bins out the night before
+    #[serde(with = "crate::ymd")]
+    pub date: Date,
+    /// The bins being collected, e.g. general and recycling
+    pub bins: Vec<BinKind>,
+}
+
+impl Display for NextCollection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let formatter = format_description::parse_borrowed::<2>(
+            "[weekday] [day padding:none] [month repr:short]",
+        )
+        .map_err(|_| std::fmt::Error)?;
+        let date = self.date.format(&formatter).map_err(|_| std::fmt::Error)?;
+        let bins = self
+            .bins
+            .iter()
+            .map(|bin| bin.to_string())
+            .collect::<Vec<_>>()
+            .join(" + ");
+        write!(f, "{date}: {bins}")
+    }
+}
+
+/// The first date on or after `from` that falls on `weekday`
+fn on_or_after(from: Date, weekday: Weekday) -> Date {
+    if from.weekday() == weekday {
+        from
+    } else {
+        from.next_occurrence(weekday)
+    }
+}
+
+/// The dates to fetch to find the next collection after `today`, see [next_collection]
+pub fn lookahead_window(today: Date) -> Result<(Date, Date), BinError> {
+    let end =
+        today
+            .checked_add(Duration::days(LOOKAHEAD_DAYS))
+            .ok_or(BinError::InvalidDateRange {
+                start: today,
+                end: today,
+            })?;
+    Ok((today, end))
+}
+
+/// Work out the next collection of bins on or after `today`, from the [Schedule] inferred from the
+/// property's bin data, looking ahead far enough to see both weeks of a fortnightly cycle. Returns `None`
+/// if there's nothing coming up.
+pub fn next_collection(
+    bin_data: &BinData,
+    bin_days: &[BinDay],
+    today: Date,
+) -> Option<NextCollection> {
+    let weeks = u32::try_from(LOOKAHEAD_DAYS / 7).unwrap_or(2);
+    let mut projected = Schedule::infer(bin_data, bin_days, today).project(bin_days, today, weeks);
+    // clean-ups and other events aren't bins to put out
+    projected.retain(|item| item.kind.is_bin());
+    let date = projected.first()?.date;
+
+    // the regular pickup first, then the rest in the order they were projected
+    let general = bin_data.kind();
+    let mut bins: Vec<BinKind> = Vec::new();
+    for item in projected.iter().filter(|item| item.date == date) {
+        if !bins.contains(&item.kind) {
+            bins.push(item.kind.clone());
+        }
+    }
+    bins.sort_by_key(|kind| *kind != general);
+    Some(NextCollection { date, bins })
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+/// Where a scheduled collection came from
+pub enum CollectionSource {
+    /// The API listed it
+    Confirmed,
+    /// We projected it from a [RecurrenceRule]
+    Predicted,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+/// A collection on a given date, either listed by the API or projected
+pub struct ScheduledCollection {
+    /// The collection date
+    #[serde(with = "crate::ymd")]
+    pub date: Date,
+    /// The bin being collected
+    pub kind: BinKind,
+    /// Whether the API listed it, or we predicted it
+    pub source: CollectionSource,
+}
+
+impl ScheduledCollection {
+    /// The colour to show this collection in: its bin day's if the API listed it, otherwise another bin
+    /// day's for the same bin, falling back to the property's colour
+    pub fn colour<'a>(&self, bin_data: &'a BinData, bin_days: &'a [BinDay]) -> &'a Rgb {
+        let same_bin = || bin_days.iter().filter(|day| day.kind() == self.kind);
+        same_bin()
+            .find(|day| day.start == self.date)
+            .or_else(|| same_bin().next())
+            .map_or(&bin_data.color, |day| &day.color)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A bin that's collected every `interval_weeks` weeks, on the same weekday as `anchor`
+pub struct RecurrenceRule {
+    /// The bin being collected
+    pub kind: BinKind,
+    /// A date the bin is known to be collected on
+    pub anchor: Date,
+    /// How many weeks between collections, 1 for weekly, 2 for fortnightly
+    pub interval_weeks: u8,
+}
+
+impl RecurrenceRule {
+    /// Does this rule have a collection on `date`?
+    pub fn occurs_on(&self, date: Date) -> bool {
+        let days = (date - self.anchor).whole_days();
+        let interval = i64::from(self.interval_weeks.max(1)) * 7;
+        days.rem_euclid(interval) == 0
+    }
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// The recurring collections for a property, inferred from what the API sent
+pub struct Schedule {
+    /// The recurrence rules, one per bin
+    pub rules: Vec<RecurrenceRule>,
+}
+
+impl Schedule {
+    /// Infer the recurrence rules for a property. The regular pickup is weekly on the property's
+    /// collection day(s), other bins recur at the spacing seen between their bin days, and a bin
+    /// that's only been seen once is assumed to alternate fortnightly, as recycling and green waste do.
+    pub fn infer(bin_data: &BinData, bin_days: &[BinDay], today: Date) -> Self {
+        let mut weekdays: Vec<Weekday> = Vec::new();
+        for day in std::iter::once(&bin_data.property.collection_day)
+            .chain(bin_data.property.collection_day_2.as_ref())
+            .chain(bin_data.dow.iter())
+            .chain(bin_data.days_of_week.iter())
+        {
+            let weekday = Weekday::from(day);
+            if !weekdays.contains(&weekday) {
+                weekdays.push(weekday);
+            }
+        }
+        let general = bin_data.kind();
+        let mut rules: Vec<RecurrenceRule> = weekdays
+            .into_iter()
+            .map(|weekday| RecurrenceRule {
+                kind: general.clone(),
+                anchor: on_or_after(today, weekday),
+                interval_weeks: 1,
+            })
+            .collect();
+
+        let mut kinds: Vec<BinKind> = Vec::new();
+        for day in bin_days {
+            let kind = day.kind();
+            if kind.is_bin() && kind != general && !kinds.contains(&kind) {
+                kinds.push(kind);
+            }
+        }
+        for kind in kinds {
+            let mut dates: Vec<Date> = bin_days
+                .iter()
+                .filter(|day| day.kind() == kind)
+                .map(|day| day.start)
+                .collect();
+            dates.sort();
+            dates.dedup();
+            let Some(anchor) = dates.first().copied() else {
+                continue;
+            };
+            let spacing = dates
+                .windows(2)
+                .map(|pair| (pair[1] - pair[0]).whole_days())
+                .fold(0, gcd);
+            let interval_weeks = match spacing {
+                0 => 2,
+                days if days % 7 == 0 => u8::try_from(days / 7).unwrap_or(u8::MAX),
+                // not a whole number of weeks apart, so we can't project it
+                _ => continue,
+            };
+            rules.push(RecurrenceRule {
+                kind,
+                anchor,
+                interval_weeks,
+            });
+        }
+        Self { rules }
+    }
+
+    /// Project collections for `weeks` weeks from `from`. Anything in `bin_days` is marked as confirmed,
+    /// and bin days that don't fit a rule (like kerbside clean-ups) are included as they are.
+    pub fn project(&self, bin_days: &[BinDay], from: Date, weeks: u32) -> Vec<ScheduledCollection> {
+        let mut result: Vec<ScheduledCollection> = bin_days
+            .iter()
+            .filter(|day| day.start >= from)
+            .map(|day| ScheduledCollection {
+                date: day.start,
+                kind: day.kind(),
+                source: CollectionSource::Confirmed,
+            })
+            .collect();
+
+        let mut date = from;
+        for _ in 0..(i64::from(weeks) * 7) {
+            for rule in self.rules.iter().filter(|rule| rule.occurs_on(date)) {
+                if !result
+                    .iter()
+                    .any(|item| item.date == date && item.kind == rule.kind)
+                {
+                    result.push(ScheduledCollection {
+                        date,
+                        kind: rule.kind.clone(),
+                        source: CollectionSource::Predicted,
+                    });
+                }
+            }
+            date = match date.next_day() {
+                Some(val) => val,
+                None => break,
+            };
+        }
+        let end = date;
+        result.retain(|item| item.date < end);
+        result.sort_by_key(|item| item.date);
+        result
+    }
+}
+
+impl BinClient {
+    /// Get the collections for a property for the next `weeks` weeks, projecting past what the API returns
+    pub async fn get_schedule(
+        &mut self,
+        property_id: u64,
+        weeks: u32,
+    ) -> Result<Vec<ScheduledCollection>, BinError> {
+        let today = self.today();
+        let (bin_data, bin_days) = self.get_property(property_id).await?;
+        let schedule = Schedule::infer(&bin_data, &bin_days, today);
+        Ok(schedule.project(&bin_days, today, weeks))
+    }
+
+    /// Get the bin data for the [lookahead_window] from today, like [BinClient::get_property_schedule].
+    /// When it comes from the cache, it only has to cover the days up to the next collection.
+    pub async fn get_upcoming_schedule(
+        &mut self,
+        property_id: u64,
+    ) -> Result<PropertySchedule, BinError> {
+        let (start, end) = lookahead_window(self.today())?;
+        self.get_schedule_or_cached(property_id, start, end, |cached| {
+            let until = cached.covered_until(start)?.min(end);
+            let bin_days = cached
+                .bin_days
+                .iter()
+                .filter(|day| (start..=until).contains(&day.start))
+                .cloned()
+                .collect::<Vec<_>>();
+            // anything projected past the covered days could miss a change the server knows about
+            next_collection(&cached.bin_data, &bin_days, start)
+                .filter(|next| next.date <= until)
+                .map(|_| until)
+        })
+        .await
+    }
+
+    /// Get the next collection for a property, and which bins go out for it
+    pub async fn get_next_collection(
+        &mut self,
+        property_id: u64,
+    ) -> Result<Option<NextCollection>, BinError> {
+        let today = self.today();
+        let schedule = self.get_upcoming_schedule(property_id).await?;
+        Ok(next_collection(
+            &schedule.bin_data,
+            &schedule.bin_days,
+            today,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fixtures, CollectionDay};
+
+    fn bin_data(collection_day_2: Option<CollectionDay>) -> BinData {
+        let mut bin_data = fixtures::bin_data();
+        bin_data.property.collection_day_2 = collection_day_2;
+        bin_data
+    }
+
+    fn date(month: time::Month, day: u8) -> Date {
+        Date::from_calendar_date(2025, month, day).expect("valid date")
+    }
+
+    #[test]
+    fn test_next_collection() {
+        let bin_days = vec![
+            fixtures::recycling("2025-10-14"),
+            fixtures::recycling("2025-10-28"),
+        ];
+
+        // Friday the 17th, so the next pickup is general only on Tuesday the 21st
+        let next = next_collection(&bin_data(None), &bin_days, date(time::Month::October, 17))
+            .expect("Should find a collection");
+        assert_eq!(next.date, date(time::Month::October, 21));
+        assert_eq!(next.bins, vec![BinKind::General]);
+        assert_eq!(next.to_string(), "Tuesday 21 Oct: general");
+
+        // the week after it's recycling too
+        let next = next_collection(&bin_data(None), &bin_days, date(time::Month::October, 22))
+            .expect("Should find a collection");
+        assert_eq!(next.to_string(), "Tuesday 28 Oct: general + recycling");
+
+        // collection day is inclusive
+        let next = next_collection(&bin_data(None), &bin_days, date(time::Month::October, 14))
+            .expect("Should find a collection");
+        assert_eq!(next.date, date(time::Month::October, 14));
+    }
+
+    #[test]
+    fn test_infer_fortnightly() {
+        let bin_days = vec![
+            fixtures::recycling("2025-10-14"),
+            fixtures::recycling("2025-10-28"),
+        ];
+        let today = date(time::Month::October, 17);
+        let schedule = Schedule::infer(&bin_data(None), &bin_days, today);
+        assert_eq!(schedule.rules.len(), 2);
+        assert_eq!(schedule.rules[0].interval_weeks, 1);
+        assert_eq!(schedule.rules[1].kind, BinKind::Recycling);
+        assert_eq!(schedule.rules[1].interval_weeks, 2);
+
+        let projected = schedule.project(&bin_days, today, 4);
+        let recycling = projected
+            .iter()
+            .filter(|item| item.kind == BinKind::Recycling)
+            .map(|item| (item.date, item.source))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            recycling,
+            vec![
+                (date(time::Month::October, 28), CollectionSource::Confirmed),
+                (date(time::Month::November, 11), CollectionSource::Predicted),
+            ]
+        );
+        let general = projected
+            .iter()
+            .filter(|item| item.kind == BinKind::General)
+            .count();
+        assert_eq!(general, 4);
+        assert!(projected
+            .iter()
+            .all(|item| item.date >= today && item.date < date(time::Month::November, 14)));
+    }
+
+    #[test]
+    fn test_infer_single_observation() {
+        let bin_days = vec![fixtures::recycling("2025-10-14")];
+        let schedule = Schedule::infer(&bin_data(None), &bin_days, date(time::Month::October, 1));
+        let rule = schedule
+            .rules
+            .iter()
+            .find(|rule| rule.kind == BinKind::Recycling)
+            .expect("Should infer recycling");
+        assert_eq!(rule.interval_weeks, 2);
+        assert!(rule.occurs_on(date(time::Month::September, 30)));
+        assert!(!rule.occurs_on(date(time::Month::October, 7)));
+    }
+
+    #[test]
+    fn test_second_collection_day() {
+        // a Friday pickup as well as Tuesday
+        let next = next_collection(
+            &bin_data(Some(CollectionDay::Friday)),
+            &[],
+            date(time::Month::October, 22),
+        )
+        .expect("Should find a collection");
+        assert_eq!(next.to_string(), "Friday 24 Oct: general");
+    }
+
+    #[test]
+    fn test_next_collection_skips_cleanups() {
+        let mut cleanup = fixtures::recycling("2025-10-20");
+        cleanup.name = Some("Kerbside Clean Up".to_string());
+        cleanup.event_type = crate::EventType::KerbsideCleanup;
+        assert_eq!(cleanup.kind(), BinKind::KerbsideCleanup);
+
+        // the clean-up on Monday the 20th comes first, but it's not a bin
+        let next = next_collection(&bin_data(None), &[cleanup], date(time::Month::October, 17))
+            .expect("Should find a collection");
+        assert_eq!(next.to_string(), "Tuesday 21 Oct: general");
+    }
+
+    #[tokio::test]
+    async fn test_upcoming_schedule_offline() {
+        use crate::cache::{tests::temp_cache, OfflineMode};
+        use crate::tests::{clock_at, stub_client};
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/properties/2695626.json"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!([fixtures::bin_data_json(),])),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        let cache = temp_cache("upcoming");
+        let client = |day: u8, offline: OfflineMode| {
+            stub_client(&server)
+                .with_clock(clock_at(2024, time::Month::January, day, 0))
+                .with_cache(cache.clone())
+                .with_offline(offline)
+        };
+        let start = Date::from_calendar_date(2024, time::Month::January, 1).expect("valid date");
+        let end = Date::from_calendar_date(2024, time::Month::January, 31).expect("valid date");
+        client(1, OfflineMode::Online)
+            .get_property_schedule(2695626, start, end)
+            .await
+            .expect("Failed to get bin data");
+
+        // on Thursday the 25th the cache stops short of the lookahead window, but has Tuesday the 30th
+        let mut offline = client(25, OfflineMode::Offline);
+        let today = Date::from_calendar_date(2024, time::Month::January, 25).expect("valid date");
+        let (window_start, window_end) = lookahead_window(today).expect("Failed to get window");
+        let err = offline
+            .get_property_schedule(2695626, window_start, window_end)
+            .await
+            .expect_err("The whole window isn't cached");
+        assert!(matches!(err, BinError::NotCached(_)));
+        let next = offline
+            .get_next_collection(2695626)
+            .await
+            .expect("Failed to use the cache offline")
+            .expect("Should find a collection");
+        assert_eq!(next.to_string(), "Tuesday 30 Jan: general");
+
+        // on Wednesday the 31st there's no collection left in what's cached
+        let err = client(31, OfflineMode::Offline)
+            .get_upcoming_schedule(2695626)
+            .await
+            .expect_err("The next collection isn't cached");
+        assert!(matches!(err, BinError::NotCached(_)));
+
+        server.verify().await;
+        let _ = std::fs::remove_dir_all(cache.dir());
+    }
+}
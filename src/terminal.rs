@@ -0,0 +1,257 @@
+//! ANSI colour output for terminals, using the colours the API sends for each bin
+
+use clap::ValueEnum;
+
+use crate::Rgb;
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// When to use colour
+pub enum ColorChoice {
+    /// When writing to a terminal, and NO_COLOR isn't set
+    #[default]
+    Auto,
+    /// Always, even when NO_COLOR is set
+    Always,
+    /// Never
+    Never,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+/// How many colours the terminal can show
+pub enum ColorDepth {
+    /// No colour at all
+    #[default]
+    None,
+    /// The 16 standard ANSI colours
+    Ansi16,
+    /// The xterm 256 colour palette
+    Ansi256,
+    /// 24-bit colour
+    TrueColor,
+}
+
+/// The 16 standard ANSI colours, as (foreground code, RGB), using the xterm palette
+const ANSI16: [(u8, (u8, u8, u8)); 16] = [
+    (30, (0, 0, 0)),
+    (31, (205, 0, 0)),
+    (32, (0, 205, 0)),
+    (33, (205, 205, 0)),
+    (34, (0, 0, 238)),
+    (35, (205, 0, 205)),
+    (36, (0, 205, 205)),
+    (37, (229, 229, 229)),
+    (90, (127, 127, 127)),
+    (91, (255, 0, 0)),
+    (92, (0, 255, 0)),
+    (93, (255, 255, 0)),
+    (94, (92, 92, 255)),
+    (95, (255, 0, 255)),
+    (96, (0, 255, 255)),
+    (97, (255, 255, 255)),
+];
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> i32 {
+    let diff = |x: u8, y: u8| (i32::from(x) - i32::from(y)).pow(2);
+    diff(a.0, b.0) + diff(a.1, b.1) + diff(a.2, b.2)
+}
+
+/// The nearest colour in the xterm 256 colour palette, from the 6x6x6 cube or the grey ramp
+fn to_ansi256(colour: &Rgb) -> u8 {
+    let rgb = (colour.red, colour.green, colour.blue);
+    let levels = [0u8, 95, 135, 175, 215, 255];
+    let nearest_level = |value: u8| {
+        levels
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, level)| (i32::from(**level) - i32::from(value)).abs())
+            .map_or(0, |(index, _)| index)
+    };
+    let (r, g, b) = (
+        nearest_level(colour.red),
+        nearest_level(colour.green),
+        nearest_level(colour.blue),
+    );
+    let cube = (levels[r], levels[g], levels[b]);
+    // r, g and b are all less than 6, so this fits in a u8
+    let cube_index = 16 + 36 * r as u8 + 6 * g as u8 + b as u8;
+
+    let average = (u16::from(colour.red) + u16::from(colour.green) + u16::from(colour.blue)) / 3;
+    let grey_step = (average.saturating_sub(8) / 10).min(23) as u8;
+    let grey_value = 8 + 10 * grey_step;
+    let grey = (grey_value, grey_value, grey_value);
+
+    if distance(rgb, grey) < distance(rgb, cube) {
+        232 + grey_step
+    } else {
+        cube_index
+    }
+}
+
+/// The nearest of the 16 standard colours, as a foreground code
+fn to_ansi16(colour: &Rgb) -> u8 {
+    let rgb = (colour.red, colour.green, colour.blue);
+    ANSI16
+        .iter()
+        .min_by_key(|(_, value)| distance(rgb, *value))
+        .map_or(37, |(code, _)| *code)
+}
+
+impl ColorDepth {
+    /// Work out the colour depth from the user's choice, whether we're writing to a terminal, and
+    /// the NO_COLOR, TERM and COLORTERM variables, read through `env`
+    pub fn detect(
+        choice: ColorChoice,
+        is_terminal: bool,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Self {
+        let set = |name: &str| env(name).is_some_and(|value| !value.is_empty());
+        match choice {
+            ColorChoice::Never => return ColorDepth::None,
+            ColorChoice::Auto => {
+                if set("NO_COLOR") || !is_terminal {
+                    return ColorDepth::None;
+                }
+            }
+            ColorChoice::Always => {}
+        }
+        let term = env("TERM").unwrap_or_default();
+        let colorterm = env("COLORTERM").unwrap_or_default().to_lowercase();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            ColorDepth::TrueColor
+        } else if term.contains("256color") {
+            ColorDepth::Ansi256
+        } else if term == "dumb" && choice == ColorChoice::Auto {
+            ColorDepth::None
+        } else {
+            ColorDepth::Ansi16
+        }
+    }
+
+    /// The escape sequence to set the foreground (or background) colour
+    fn escape(self, colour: &Rgb, background: bool) -> String {
+        match self {
+            ColorDepth::None => String::new(),
+            ColorDepth::Ansi16 => {
+                let code = to_ansi16(colour);
+                format!("\x1b[{}m", if background { code + 10 } else { code })
+            }
+            ColorDepth::Ansi256 => format!(
+                "\x1b[{};5;{}m",
+                if background { 48 } else { 38 },
+                to_ansi256(colour)
+            ),
+            ColorDepth::TrueColor => format!(
+                "\x1b[{};2;{};{};{}m",
+                if background { 48 } else { 38 },
+                colour.red,
+                colour.green,
+                colour.blue
+            ),
+        }
+    }
+
+    /// Write `text` in the given colour, or leave it as-is without colour
+    pub fn paint(self, text: &str, foreground: &Rgb) -> String {
+        if self == ColorDepth::None {
+            return text.to_string();
+        }
+        format!("{}{text}\x1b[0m", self.escape(foreground, false))
+    }
+
+    /// Make `text` stand out, in reverse video, or leave it as-is without colour
+    pub fn highlight(self, text: &str) -> String {
+        if self == ColorDepth::None {
+            return text.to_string();
+        }
+        format!("\x1b[1;7m{text}\x1b[0m")
+    }
+
+    /// Wrap `text` in a badge with the given background and text colours, or leave it as-is without colour
+    pub fn badge(self, text: &str, background: &Rgb, foreground: &Rgb) -> String {
+        if self == ColorDepth::None {
+            return text.to_string();
+        }
+        format!(
+            "{}{}{text}\x1b[0m",
+            self.escape(background, true),
+            self.escape(foreground, false)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env<'a>(vars: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| {
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    #[test]
+    fn test_detect() {
+        let truecolor = [("TERM", "xterm-256color"), ("COLORTERM", "truecolor")];
+        assert_eq!(
+            ColorDepth::detect(ColorChoice::Auto, true, env(&truecolor)),
+            ColorDepth::TrueColor
+        );
+        assert_eq!(
+            ColorDepth::detect(ColorChoice::Auto, false, env(&truecolor)),
+            ColorDepth::None
+        );
+        assert_eq!(
+            ColorDepth::detect(ColorChoice::Never, true, env(&truecolor)),
+            ColorDepth::None
+        );
+        assert_eq!(
+            ColorDepth::detect(ColorChoice::Auto, true, env(&[("TERM", "xterm-256color")])),
+            ColorDepth::Ansi256
+        );
+        assert_eq!(
+            ColorDepth::detect(ColorChoice::Auto, true, env(&[("TERM", "xterm")])),
+            ColorDepth::Ansi16
+        );
+
+        let no_color = [("TERM", "xterm-256color"), ("NO_COLOR", "1")];
+        assert_eq!(
+            ColorDepth::detect(ColorChoice::Auto, true, env(&no_color)),
+            ColorDepth::None
+        );
+        assert_eq!(
+            ColorDepth::detect(ColorChoice::Always, false, env(&no_color)),
+            ColorDepth::Ansi256
+        );
+    }
+
+    #[test]
+    fn test_nearest_colours() {
+        assert_eq!(to_ansi256(&Rgb::new(255, 0, 0)), 196);
+        assert_eq!(to_ansi256(&Rgb::new(0x5e, 0x5e, 0x5e)), 59);
+        assert_eq!(to_ansi256(&Rgb::new(128, 128, 128)), 244);
+        assert_eq!(to_ansi16(&Rgb::new(255, 215, 0)), 93);
+        assert_eq!(to_ansi16(&Rgb::new(0, 0, 0)), 30);
+    }
+
+    #[test]
+    fn test_badge() {
+        let yellow = Rgb::new(255, 215, 0);
+        let black = Rgb::new(0, 0, 0);
+        assert_eq!(ColorDepth::None.badge("hi", &yellow, &black), "hi");
+        assert_eq!(
+            ColorDepth::TrueColor.badge("hi", &yellow, &black),
+            "\x1b[48;2;255;215;0m\x1b[38;2;0;0;0mhi\x1b[0m"
+        );
+        assert_eq!(
+            ColorDepth::Ansi16.badge("hi", &yellow, &black),
+            "\x1b[103m\x1b[30mhi\x1b[0m"
+        );
+        assert_eq!(
+            ColorDepth::Ansi256.paint("hi", &Rgb::new(255, 0, 0)),
+            "\x1b[38;5;196mhi\x1b[0m"
+        );
+        assert_eq!(ColorDepth::None.highlight("hi"), "hi");
+    }
+}